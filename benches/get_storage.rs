@@ -0,0 +1,65 @@
+// Run with: cargo bench --bench get_storage
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::hint::black_box;
+use trait_type_map::{
+    impl_trait_accessible, DefaultHashBuilder, TraitTypeMap, TypeIdBuildHasher, VecFamily,
+};
+
+trait Component {
+    fn value(&self) -> u32;
+}
+
+macro_rules! components {
+    ($($ty:ident),+) => {
+        $(
+            struct $ty(u32);
+            impl Component for $ty {
+                fn value(&self) -> u32 {
+                    self.0
+                }
+            }
+        )+
+        impl_trait_accessible!(dyn Component; $($ty),+);
+
+        fn populated<S: BuildHasher>(hash_builder: S) -> TraitTypeMap<dyn Component, VecFamily, S> {
+            let mut map: TraitTypeMap<dyn Component, VecFamily, S> =
+                TraitTypeMap::with_hasher(hash_builder);
+            $(
+                map.register_type_storage::<$ty>();
+                map.get_storage_mut::<$ty>().push($ty(1));
+            )+
+            map
+        }
+    };
+}
+
+components!(C0, C1, C2, C3, C4, C5, C6, C7, C8, C9, C10, C11, C12, C13, C14, C15);
+
+/// Look up a few storages spread over the registered types.
+fn lookups<S: BuildHasher>(map: &TraitTypeMap<dyn Component, VecFamily, S>) -> u32 {
+    map.get_storage::<C0>().get(0).value()
+        + map.get_storage::<C5>().get(0).value()
+        + map.get_storage::<C10>().get(0).value()
+        + map.get_storage::<C15>().get(0).value()
+}
+
+fn bench_get_storage(c: &mut Criterion) {
+    let mut group = c.benchmark_group("get_storage");
+
+    let map = populated(DefaultHashBuilder::default());
+    group.bench_function("default", |b| b.iter(|| lookups(black_box(&map))));
+
+    let map = populated(TypeIdBuildHasher::default());
+    group.bench_function("type_id", |b| b.iter(|| lookups(black_box(&map))));
+
+    let map = populated(RandomState::new());
+    group.bench_function("std", |b| b.iter(|| lookups(black_box(&map))));
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_get_storage);
+criterion_main!(benches);
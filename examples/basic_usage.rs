@@ -131,6 +131,17 @@ fn vector_storage_example() {
         println!("  Cat {}: {} says {}", i, cat.name(), cat.speak());
     }
 
+    // Iterate over every animal regardless of its type
+    println!("\nIterating over all animals:");
+    for (_, idx, animal) in map.iter_dyn() {
+        println!(
+            "  Animal at index {}: {} says {}",
+            idx,
+            animal.name(),
+            animal.speak()
+        );
+    }
+
     // Take ownership (removes from storage)
     println!("\nTaking ownership of a dog:");
     let dog_storage_mut = map.get_storage_mut::<Dog>();
@@ -0,0 +1,110 @@
+// Run with: cargo run --example derive_usage --features derive
+
+use std::fmt::Display;
+use trait_type_map::{trait_accessible, TraitAccessible, TraitTypeMap, VecFamily};
+
+// Define the traits our types will be accessible as
+trait Shape {
+    fn area(&self) -> f32;
+}
+
+trait Describe {
+    fn describe(&self) -> String;
+}
+
+// The attribute sits on the type itself, so it cannot drift out of sync
+#[trait_accessible(dyn Shape, dyn Describe)]
+struct Circle {
+    radius: f32,
+}
+
+impl Shape for Circle {
+    fn area(&self) -> f32 {
+        std::f32::consts::PI * self.radius * self.radius
+    }
+}
+
+impl Describe for Circle {
+    fn describe(&self) -> String {
+        format!("circle with radius {}", self.radius)
+    }
+}
+
+// The derive form works the same way
+#[derive(TraitAccessible)]
+#[accessible_as(dyn Shape)]
+struct Rect {
+    w: f32,
+    h: f32,
+}
+
+impl Shape for Rect {
+    fn area(&self) -> f32 {
+        self.w * self.h
+    }
+}
+
+// Generic types with where clauses are supported
+#[trait_accessible(dyn Shape, dyn Describe)]
+struct Labeled<S, L>
+where
+    S: Shape,
+    L: Display,
+{
+    shape: S,
+    label: L,
+}
+
+impl<S: Shape, L: Display> Shape for Labeled<S, L> {
+    fn area(&self) -> f32 {
+        self.shape.area()
+    }
+}
+
+impl<S: Shape, L: Display> Describe for Labeled<S, L> {
+    fn describe(&self) -> String {
+        format!("{} ({:.2})", self.label, self.shape.area())
+    }
+}
+
+fn main() {
+    let mut shapes: TraitTypeMap<dyn Shape, VecFamily> = TraitTypeMap::new();
+    shapes.register_type_storage::<Circle>();
+    shapes.register_type_storage::<Rect>();
+    shapes.register_type_storage::<Labeled<Rect, &'static str>>();
+
+    shapes
+        .get_storage_mut::<Circle>()
+        .push(Circle { radius: 1.0 });
+    shapes
+        .get_storage_mut::<Rect>()
+        .push(Rect { w: 2.0, h: 3.0 });
+    shapes
+        .get_storage_mut::<Labeled<Rect, &'static str>>()
+        .push(Labeled {
+            shape: Rect { w: 1.0, h: 4.0 },
+            label: "door",
+        });
+
+    let total: f32 = shapes.iter_dyn().map(|(_, _, shape)| shape.area()).sum();
+    println!("Total area: {total:.2}");
+
+    // The same types can be stored in a map over a different trait
+    let mut descriptions: TraitTypeMap<dyn Describe, VecFamily> = TraitTypeMap::new();
+    descriptions.register_type_storage::<Circle>();
+    descriptions.register_type_storage::<Labeled<Circle, u32>>();
+
+    descriptions
+        .get_storage_mut::<Circle>()
+        .push(Circle { radius: 2.0 });
+    descriptions
+        .get_storage_mut::<Labeled<Circle, u32>>()
+        .push(Labeled {
+            shape: Circle { radius: 0.5 },
+            label: 7,
+        });
+
+    for (_, _, item) in descriptions.iter_dyn() {
+        println!("{}", item.describe());
+    }
+}
@@ -0,0 +1,218 @@
+use crate::{
+    AsAny, ChangeFlags, DefaultHashBuilder, StorageFamily, TraitAccessible, TraitTypeMap,
+    TraitTypeMapError,
+};
+use std::any::TypeId;
+use std::fmt;
+use std::hash::BuildHasher;
+use std::ops::Deref;
+
+/// A [`TraitTypeMap`] that only accepts `Clone` types, so the whole map is `Clone`.
+///
+/// Every type is registered as cloneable, which makes cloning infallible, e.g. to snapshot a
+/// registry for undo. Read access goes through `Deref` to the inner map; the mutating methods
+/// that cannot register a type are forwarded, and [`into_inner`](Self::into_inner) /
+/// [`TryFrom`] convert to and from a plain map for everything else.
+///
+/// Like [`TraitTypeMap::try_clone`], clones start without hooks.
+///
+/// # Examples
+///
+/// ```rust
+/// use trait_type_map::{impl_trait_accessible, CloneableTraitTypeMap, TraitTypeMap, VecFamily};
+///
+/// trait Plugin {
+///     fn level(&self) -> u32;
+/// }
+/// #[derive(Clone)]
+/// struct Volume(u32);
+/// impl Plugin for Volume {
+///     fn level(&self) -> u32 { self.0 }
+/// }
+/// struct Socket;
+/// impl Plugin for Socket {
+///     fn level(&self) -> u32 { 0 }
+/// }
+/// impl_trait_accessible!(dyn Plugin; Volume, Socket);
+///
+/// let mut map: CloneableTraitTypeMap<dyn Plugin, VecFamily> = CloneableTraitTypeMap::new();
+/// map.register_type_storage::<Volume>();
+/// map.get_storage_mut::<Volume>().push(Volume(3));
+///
+/// let undo = map.clone();
+/// map.get_storage_mut::<Volume>().get_mut(0).0 = 11;
+///
+/// assert_eq!(undo.get_storage::<Volume>().get(0).level(), 3);
+/// assert_eq!(map.get_storage::<Volume>().get(0).level(), 11);
+///
+/// // A plain map converts only if every type was registered as cloneable
+/// let mut plain: TraitTypeMap<dyn Plugin, VecFamily> = TraitTypeMap::new();
+/// plain.register_type_storage::<Socket>();
+/// assert!(CloneableTraitTypeMap::try_from(plain).is_err());
+/// ```
+pub struct CloneableTraitTypeMap<
+    Dyn: ?Sized + 'static,
+    F: StorageFamily<Dyn>,
+    S = DefaultHashBuilder,
+> {
+    /// Only ever holds types registered with `register_cloneable_type_storage`.
+    map: TraitTypeMap<Dyn, F, S>,
+}
+
+impl<Dyn: ?Sized + 'static, F: StorageFamily<Dyn>, S: BuildHasher + Clone> Clone
+    for CloneableTraitTypeMap<Dyn, F, S>
+{
+    fn clone(&self) -> Self {
+        let map = self
+            .map
+            .try_clone()
+            .unwrap_or_else(|_| unreachable!("every registered type is cloneable"));
+        Self { map }
+    }
+}
+
+impl<Dyn: ?Sized + 'static, F: StorageFamily<Dyn>, S: BuildHasher + Default> Default
+    for CloneableTraitTypeMap<Dyn, F, S>
+{
+    fn default() -> Self {
+        Self::with_hasher(S::default())
+    }
+}
+
+impl<Dyn: ?Sized + 'static, F: StorageFamily<Dyn>, S: BuildHasher> fmt::Debug
+    for CloneableTraitTypeMap<Dyn, F, S>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CloneableTraitTypeMap")
+            .field(
+                "types",
+                &self
+                    .registered_types()
+                    .map(|(_, name)| name)
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+impl<Dyn: ?Sized + 'static, F: StorageFamily<Dyn>, S> Deref for CloneableTraitTypeMap<Dyn, F, S> {
+    type Target = TraitTypeMap<Dyn, F, S>;
+
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target {
+        &self.map
+    }
+}
+
+/// Fails with [`TraitTypeMapError::NotCloneable`] if any type was not registered as cloneable.
+impl<Dyn: ?Sized + 'static, F: StorageFamily<Dyn>, S: BuildHasher> TryFrom<TraitTypeMap<Dyn, F, S>>
+    for CloneableTraitTypeMap<Dyn, F, S>
+{
+    type Error = TraitTypeMapError;
+
+    fn try_from(map: TraitTypeMap<Dyn, F, S>) -> Result<Self, Self::Error> {
+        map.check_cloneable()?;
+        Ok(Self { map })
+    }
+}
+
+impl<Dyn: ?Sized + 'static, F: StorageFamily<Dyn>> CloneableTraitTypeMap<Dyn, F> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new map with pre-allocated capacity for the given number of types.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_and_hasher(capacity, DefaultHashBuilder::default())
+    }
+}
+
+impl<Dyn: ?Sized + 'static, F: StorageFamily<Dyn>, S: BuildHasher>
+    CloneableTraitTypeMap<Dyn, F, S>
+{
+    /// Create a new map that hashes `TypeId`s with `hash_builder`, e.g. [`TypeIdBuildHasher`](crate::TypeIdBuildHasher).
+    pub fn with_hasher(hash_builder: S) -> Self {
+        Self::with_capacity_and_hasher(0, hash_builder)
+    }
+
+    /// Create a new map with pre-allocated capacity that hashes `TypeId`s with `hash_builder`.
+    pub fn with_capacity_and_hasher(capacity: usize, hash_builder: S) -> Self {
+        Self {
+            map: TraitTypeMap::with_capacity_and_hasher(capacity, hash_builder),
+        }
+    }
+
+    /// Unwrap the inner map, e.g. to use mutating methods that are not forwarded.
+    pub fn into_inner(self) -> TraitTypeMap<Dyn, F, S> {
+        self.map
+    }
+
+    /// Register a storage for `T`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `T` is already registered. See [`try_register_type_storage`](Self::try_register_type_storage).
+    pub fn register_type_storage<T>(&mut self)
+    where
+        T: 'static + Clone + TraitAccessible<Dyn>,
+    {
+        self.map.register_cloneable_type_storage::<T>();
+    }
+
+    /// Register a storage for `T`, failing with [`TraitTypeMapError::AlreadyRegistered`]
+    /// instead of panicking if it already exists.
+    pub fn try_register_type_storage<T>(&mut self) -> Result<(), TraitTypeMapError>
+    where
+        T: 'static + Clone + TraitAccessible<Dyn>,
+    {
+        self.map.try_register_cloneable_type_storage::<T>()
+    }
+
+    /// See [`TraitTypeMap::unregister_type_storage`].
+    pub fn unregister_type_storage<T: 'static>(&mut self) -> Option<Box<F::Trait>> {
+        self.map.unregister_type_storage::<T>()
+    }
+
+    /// See [`TraitTypeMap::get_storage_mut`].
+    #[inline(always)]
+    pub fn get_storage_mut<T: 'static>(&mut self) -> &mut F::Storage<T> {
+        self.map.get_storage_mut::<T>()
+    }
+
+    /// See [`TraitTypeMap::try_get_storage_mut`].
+    #[inline(always)]
+    pub fn try_get_storage_mut<T: 'static>(
+        &mut self,
+    ) -> Result<&mut F::Storage<T>, TraitTypeMapError> {
+        self.map.try_get_storage_mut::<T>()
+    }
+
+    /// See [`TraitTypeMap::get_trait_storage_mut`].
+    #[inline(always)]
+    pub fn get_trait_storage_mut(&mut self, id: TypeId) -> Option<&mut F::Trait> {
+        self.map.get_trait_storage_mut(id)
+    }
+
+    /// See [`TraitTypeMap::insert_boxed`].
+    pub fn insert_boxed(&mut self, value: Box<Dyn>) -> Result<usize, Box<Dyn>>
+    where
+        Dyn: AsAny,
+    {
+        self.map.insert_boxed(value)
+    }
+
+    /// See [`TraitTypeMap::iter_dyn_mut`].
+    pub fn iter_dyn_mut(&mut self) -> impl Iterator<Item = (TypeId, usize, &mut Dyn)> + '_ {
+        self.map.iter_dyn_mut()
+    }
+
+    /// See [`TraitTypeMap::increment_change_tick`].
+    pub fn increment_change_tick(&mut self) -> u64 {
+        self.map.increment_change_tick()
+    }
+
+    /// See [`TraitTypeMap::drain_changes`].
+    pub fn drain_changes(&mut self) -> Vec<(TypeId, usize, ChangeFlags)> {
+        self.map.drain_changes()
+    }
+}
@@ -0,0 +1,51 @@
+use std::fmt;
+
+/// Errors returned by the non-panicking (`try_*`) API of `TraitTypeMap` and its storages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TraitTypeMapError {
+    /// The requested type has no storage registered in the map.
+    NotRegistered { type_name: &'static str },
+    /// A storage for the type has already been registered.
+    AlreadyRegistered { type_name: &'static str },
+    /// An index was outside of the storage bounds.
+    IndexOutOfBounds { index: usize, len: usize },
+    /// The registered storage does not hold the requested concrete type.
+    WrongStorageType {
+        expected: &'static str,
+        found: &'static str,
+    },
+    /// The type was not registered as cloneable, so the map cannot be cloned.
+    NotCloneable { type_name: &'static str },
+    /// The storage is currently locked by another borrow.
+    StorageLocked { type_name: &'static str },
+    /// The same type was requested more than once in a single multi-storage borrow.
+    AliasedStorage { type_name: &'static str },
+}
+
+impl fmt::Display for TraitTypeMapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotRegistered { type_name } => write!(f, "type not registered: {type_name}"),
+            Self::AlreadyRegistered { type_name } => {
+                write!(f, "type already registered: {type_name}")
+            }
+            Self::IndexOutOfBounds { index, len } => {
+                write!(
+                    f,
+                    "index out of bounds: the len is {len} but the index is {index}"
+                )
+            }
+            Self::WrongStorageType { expected, found } => {
+                write!(f, "wrong storage type: expected {expected}, found {found}")
+            }
+            Self::NotCloneable { type_name } => write!(f, "type not cloneable: {type_name}"),
+            Self::StorageLocked { type_name } => write!(f, "storage locked: {type_name}"),
+            Self::AliasedStorage { type_name } => {
+                write!(f, "storage borrowed more than once: {type_name}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for TraitTypeMapError {}
@@ -0,0 +1,63 @@
+use std::hash::{BuildHasherDefault, Hasher};
+
+/// Hash builder used by [`TraitTypeMap`](crate::TraitTypeMap) and the keyed storages when none is given.
+///
+/// `ahash::RandomState` with the `ahash` feature (enabled by default), otherwise the standard library's `RandomState`.
+#[cfg(feature = "ahash")]
+pub type DefaultHashBuilder = ahash::RandomState;
+
+/// Hash builder used by [`TraitTypeMap`](crate::TraitTypeMap) and the keyed storages when none is given.
+///
+/// `ahash::RandomState` with the `ahash` feature (enabled by default), otherwise the standard library's `RandomState`.
+#[cfg(not(feature = "ahash"))]
+pub type DefaultHashBuilder = std::collections::hash_map::RandomState;
+
+pub(crate) type HashMap<K, V> = std::collections::HashMap<K, V, DefaultHashBuilder>;
+
+/// Hash builder for [`TypeIdHasher`].
+pub type TypeIdBuildHasher = BuildHasherDefault<TypeIdHasher>;
+
+/// Identity hasher for `TypeId` keys.
+///
+/// A `TypeId` is already a hash of its type, so hashing it again is wasted work; this hasher
+/// passes it through unchanged. Other keys are folded in with a cheap rotate-and-xor, which is
+/// not collision resistant, so only use it for maps keyed by `TypeId`.
+///
+/// # Examples
+///
+/// ```rust
+/// use trait_type_map::{impl_trait_accessible, TraitTypeMap, TypeIdBuildHasher, VecFamily};
+///
+/// trait Plugin {}
+/// struct Physics;
+/// impl Plugin for Physics {}
+/// impl_trait_accessible!(dyn Plugin; Physics);
+///
+/// let mut map: TraitTypeMap<dyn Plugin, VecFamily, TypeIdBuildHasher> =
+///     TraitTypeMap::with_hasher(TypeIdBuildHasher::default());
+/// map.register_type_storage::<Physics>();
+/// map.get_storage_mut::<Physics>().push(Physics);
+/// assert_eq!(map.get_storage::<Physics>().data.len(), 1);
+/// ```
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TypeIdHasher {
+    hash: u64,
+}
+
+impl Hasher for TypeIdHasher {
+    #[inline]
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+
+    #[inline]
+    fn write_u64(&mut self, n: u64) {
+        self.hash = self.hash.rotate_left(5) ^ n;
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.write_u64(u64::from(b));
+        }
+    }
+}
@@ -9,6 +9,7 @@
 //! - **Flexible storage backends**: Choose between single-value (`SingleFamily`) or multi-value (`VecFamily`) storage per type
 //! - **Trait object access**: Access stored values as trait objects without knowing the concrete type
 //! - **Type-indexed retrieval**: Retrieve values by their concrete type with zero runtime overhead
+//! - **Non-panicking API**: Every panicking accessor has a `try_*` counterpart returning [`TraitTypeMapError`]
 //!
 //! ## Quick Start
 //!
@@ -48,11 +49,29 @@
 //!
 //! // Access via trait object
 //! let dog_storage = map.get_storage::<Dog>();
-//! if let Some(animal) = dog_storage.get_dyn(dog_idx) {
-//!     assert_eq!(animal.speak(), "Woof!");
-//! }
+//! assert_eq!(dog_storage.get_dyn(dog_idx).speak(), "Woof!");
+//!
+//! // Checked access never panics
+//! assert!(dog_storage.try_get_dyn(cat_idx + 1).is_err());
 //! # }
 //! ```
 
+mod cloneable_trait_type_map;
+mod error;
+mod hasher;
+#[cfg(feature = "rayon")]
+mod parallel;
+#[cfg(feature = "serde")]
+mod serde_support;
+mod sync_trait_type_map;
 mod trait_type_map;
+pub use cloneable_trait_type_map::*;
+pub use dyn_clone;
+pub use error::*;
+pub use hasher::*;
+#[cfg(feature = "serde")]
+pub use serde_support::*;
+pub use sync_trait_type_map::*;
 pub use trait_type_map::*;
+#[cfg(feature = "derive")]
+pub use trait_type_map_derive::{trait_accessible, TraitAccessible};
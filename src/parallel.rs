@@ -0,0 +1,121 @@
+use crate::{SparseSetStorage, SyncStorageFamily, SyncTraitTypeMap, VecOptionStorage, VecStorage};
+use rayon::prelude::*;
+use std::any::TypeId;
+use std::hash::BuildHasher;
+
+/* ==================== Storage iteration ==================== */
+
+impl<T, Dyn: ?Sized> VecStorage<T, Dyn> {
+    /// Parallel counterpart of [`iter`](Self::iter).
+    pub fn par_iter(&self) -> impl IndexedParallelIterator<Item = &T>
+    where
+        T: Sync,
+    {
+        self.data.par_iter()
+    }
+
+    /// Iterate mutably over all stored values in parallel.
+    pub fn par_iter_mut(&mut self) -> impl IndexedParallelIterator<Item = &mut T>
+    where
+        T: Send,
+    {
+        self.data.par_iter_mut()
+    }
+}
+
+impl<T, Dyn: ?Sized> VecOptionStorage<T, Dyn> {
+    /// Parallel counterpart of [`iter`](Self::iter), skipping holes.
+    pub fn par_iter(&self) -> impl ParallelIterator<Item = &T>
+    where
+        T: Sync,
+    {
+        self.data.par_iter().filter_map(|o| o.as_ref())
+    }
+
+    /// Iterate mutably over all stored values in parallel, skipping holes.
+    ///
+    /// With change tracking enabled, every live slot is marked [`MODIFIED`](crate::ChangeFlags::MODIFIED).
+    pub fn par_iter_mut(&mut self) -> impl ParallelIterator<Item = &mut T>
+    where
+        T: Send,
+    {
+        self.mark_live_modified();
+        self.data.par_iter_mut().filter_map(|o| o.as_mut())
+    }
+}
+
+impl<T, Dyn: ?Sized> SparseSetStorage<T, Dyn> {
+    /// Parallel counterpart of [`iter`](Self::iter), over the dense array.
+    pub fn par_iter(&self) -> impl IndexedParallelIterator<Item = &T>
+    where
+        T: Sync,
+    {
+        self.as_slice().par_iter()
+    }
+
+    /// Iterate mutably over all stored values in parallel, over the dense array.
+    ///
+    /// With change tracking enabled, every key is marked [`MODIFIED`](crate::ChangeFlags::MODIFIED).
+    pub fn par_iter_mut(&mut self) -> impl IndexedParallelIterator<Item = &mut T>
+    where
+        T: Send,
+    {
+        self.as_mut_slice_tracked().par_iter_mut()
+    }
+}
+
+/* ====================== Map iteration ====================== */
+
+impl<Dyn: ?Sized + 'static, F: SyncStorageFamily<Dyn>, S: BuildHasher> SyncTraitTypeMap<Dyn, F, S> {
+    /// Call `f` with `(TypeId, index, &mut Dyn)` for every stored value, processing
+    /// the storages of different types in parallel.
+    ///
+    /// Values of the same type are visited sequentially, in index order.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use trait_type_map::{impl_trait_accessible, SyncTraitTypeMap, VecFamily};
+    ///
+    /// trait Body {
+    ///     fn step(&mut self);
+    ///     fn pos(&self) -> f32;
+    /// }
+    ///
+    /// struct Ball(f32);
+    /// impl Body for Ball {
+    ///     fn step(&mut self) { self.0 += 1.0; }
+    ///     fn pos(&self) -> f32 { self.0 }
+    /// }
+    ///
+    /// struct Rock(f32);
+    /// impl Body for Rock {
+    ///     fn step(&mut self) {}
+    ///     fn pos(&self) -> f32 { self.0 }
+    /// }
+    ///
+    /// impl_trait_accessible!(dyn Body; Ball, Rock);
+    ///
+    /// let mut map: SyncTraitTypeMap<dyn Body, VecFamily> = SyncTraitTypeMap::new();
+    /// map.register_type_storage::<Ball>();
+    /// map.register_type_storage::<Rock>();
+    /// map.get_storage_mut::<Ball>().push(Ball(0.0));
+    /// map.get_storage_mut::<Rock>().push(Rock(5.0));
+    ///
+    /// map.par_for_each_dyn_mut(|_, _, body| body.step());
+    ///
+    /// assert_eq!(map.read::<Ball>().get(0).pos(), 1.0);
+    /// assert_eq!(map.read::<Rock>().get(0).pos(), 5.0);
+    /// ```
+    pub fn par_for_each_dyn_mut<Func>(&mut self, f: Func)
+    where
+        Func: Fn(TypeId, usize, &mut Dyn) + Send + Sync,
+    {
+        let storages: Vec<_> = self.storages_mut().collect();
+        storages.into_par_iter().for_each(|(id, storage)| {
+            for (i, value) in F::iter_dyn_mut(F::as_trait_mut(storage)) {
+                f(id, i, value);
+            }
+        });
+    }
+}
@@ -0,0 +1,503 @@
+use crate::hasher::HashMap;
+use crate::{
+    CloneStorageFn, DefaultHashBuilder, HashMapFamily, KeyedStorage, OptionFamily, OptionStorage,
+    OrderedFamily, OrderedStorage, SparseSetFamily, SparseSetStorage, StorageFamily,
+    TraitAccessible, TraitAccessor, TraitTypeMap, VecFamily, VecOptionFamily, VecOptionStorage,
+    VecStorage,
+};
+use serde::de::{self, DeserializeOwned, DeserializeSeed, MapAccess, Visitor};
+use serde::ser::{self, SerializeMap};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::any::{type_name, TypeId};
+use std::fmt;
+use std::hash::{BuildHasher, Hash};
+
+/* ==================== Storage serialization ==================== */
+
+impl<T: Serialize, Dyn: ?Sized> Serialize for VecStorage<T, Dyn> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.data.serialize(serializer)
+    }
+}
+
+/// Serialized form of a [`VecOptionStorage`] or [`SparseSetStorage`].
+#[derive(Serialize)]
+struct VecOptionSlotsRef<'a, T> {
+    slots: &'a [Option<T>],
+    generations: &'a [u32],
+}
+
+/// Deserialized form of a [`VecOptionStorage`] or [`SparseSetStorage`].
+#[derive(Deserialize)]
+struct VecOptionSlots<T> {
+    slots: Vec<Option<T>>,
+    generations: Vec<u32>,
+}
+
+/// Serialized as `{slots, generations}`: a sequence of optional values, so holes keep their
+/// positions, and the generation of each slot, so handles stay valid.
+impl<T: Serialize, Dyn: ?Sized> Serialize for VecOptionStorage<T, Dyn> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        VecOptionSlotsRef {
+            slots: &self.data,
+            generations: self.generations(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<T: Serialize, Dyn: ?Sized> Serialize for OptionStorage<T, Dyn> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.data.serialize(serializer)
+    }
+}
+
+/// Serialized like [`VecOptionStorage`], with the slots laid out by key.
+impl<T: Serialize, Dyn: ?Sized> Serialize for SparseSetStorage<T, Dyn> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let generations = self.generations();
+        let slots: Vec<Option<&T>> = (0..generations.len()).map(|key| self.get(key)).collect();
+        VecOptionSlotsRef {
+            slots: &slots,
+            generations,
+        }
+        .serialize(serializer)
+    }
+}
+
+/// Serialized as a map from keys to values, in insertion order.
+impl<K: Serialize, T: Serialize, Dyn: ?Sized> Serialize for KeyedStorage<K, T, Dyn> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.iter())
+    }
+}
+
+/// Serialized as a sequence of `(priority, value)` pairs, in order.
+impl<P: Serialize, T: Serialize, Dyn: ?Sized> Serialize for OrderedStorage<P, T, Dyn> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+/// Storage families whose storages can be serialized and deserialized.
+pub trait SerdeFamily<Dyn: ?Sized + 'static>: StorageFamily<Dyn> {
+    fn erased_serialize<T: Serialize + 'static>(e: &Self::Trait) -> &dyn erased_serde::Serialize;
+    fn erased_deserialize<T: DeserializeOwned + 'static>(
+        trait_accessor: TraitAccessor<T, Dyn>,
+        deserializer: &mut dyn erased_serde::Deserializer<'_>,
+    ) -> Result<Box<Self::Trait>, erased_serde::Error>;
+}
+
+impl<D: ?Sized + 'static> SerdeFamily<D> for VecFamily {
+    fn erased_serialize<T: Serialize + 'static>(e: &Self::Trait) -> &dyn erased_serde::Serialize {
+        Self::storage_ref::<T>(e)
+    }
+
+    fn erased_deserialize<T: DeserializeOwned + 'static>(
+        trait_accessor: TraitAccessor<T, D>,
+        deserializer: &mut dyn erased_serde::Deserializer<'_>,
+    ) -> Result<Box<Self::Trait>, erased_serde::Error> {
+        let mut storage = VecStorage::new(trait_accessor);
+        storage.data = erased_serde::deserialize(deserializer)?;
+        Ok(Box::new(storage))
+    }
+}
+
+impl<D: ?Sized + 'static> SerdeFamily<D> for VecOptionFamily {
+    fn erased_serialize<T: Serialize + 'static>(e: &Self::Trait) -> &dyn erased_serde::Serialize {
+        Self::storage_ref::<T>(e)
+    }
+
+    fn erased_deserialize<T: DeserializeOwned + 'static>(
+        trait_accessor: TraitAccessor<T, D>,
+        deserializer: &mut dyn erased_serde::Deserializer<'_>,
+    ) -> Result<Box<Self::Trait>, erased_serde::Error> {
+        let VecOptionSlots { slots, generations } = erased_serde::deserialize(deserializer)?;
+        Ok(Box::new(VecOptionStorage::from_slots_and_generations(
+            trait_accessor,
+            slots,
+            generations,
+        )))
+    }
+}
+
+impl<D: ?Sized + 'static> SerdeFamily<D> for OptionFamily {
+    fn erased_serialize<T: Serialize + 'static>(e: &Self::Trait) -> &dyn erased_serde::Serialize {
+        Self::storage_ref::<T>(e)
+    }
+
+    fn erased_deserialize<T: DeserializeOwned + 'static>(
+        trait_accessor: TraitAccessor<T, D>,
+        deserializer: &mut dyn erased_serde::Deserializer<'_>,
+    ) -> Result<Box<Self::Trait>, erased_serde::Error> {
+        let mut storage = OptionStorage::new(trait_accessor);
+        storage.data = erased_serde::deserialize(deserializer)?;
+        Ok(Box::new(storage))
+    }
+}
+
+impl<D: ?Sized + 'static> SerdeFamily<D> for SparseSetFamily {
+    fn erased_serialize<T: Serialize + 'static>(e: &Self::Trait) -> &dyn erased_serde::Serialize {
+        Self::storage_ref::<T>(e)
+    }
+
+    fn erased_deserialize<T: DeserializeOwned + 'static>(
+        trait_accessor: TraitAccessor<T, D>,
+        deserializer: &mut dyn erased_serde::Deserializer<'_>,
+    ) -> Result<Box<Self::Trait>, erased_serde::Error> {
+        let VecOptionSlots { slots, generations } = erased_serde::deserialize(deserializer)?;
+        Ok(Box::new(SparseSetStorage::from_slots_and_generations(
+            trait_accessor,
+            slots,
+            generations,
+        )))
+    }
+}
+
+impl<D, K> SerdeFamily<D> for HashMapFamily<K>
+where
+    D: ?Sized + 'static,
+    K: Hash + Eq + Clone + Serialize + DeserializeOwned + 'static,
+{
+    fn erased_serialize<T: Serialize + 'static>(e: &Self::Trait) -> &dyn erased_serde::Serialize {
+        Self::storage_ref::<T>(e)
+    }
+
+    fn erased_deserialize<T: DeserializeOwned + 'static>(
+        trait_accessor: TraitAccessor<T, D>,
+        deserializer: &mut dyn erased_serde::Deserializer<'_>,
+    ) -> Result<Box<Self::Trait>, erased_serde::Error> {
+        let data = erased_serde::deserialize(deserializer)?;
+        Ok(Box::new(KeyedStorage::from_map(trait_accessor, data)))
+    }
+}
+
+impl<D, P> SerdeFamily<D> for OrderedFamily<P>
+where
+    D: ?Sized + 'static,
+    P: Ord + Clone + Serialize + DeserializeOwned + 'static,
+{
+    fn erased_serialize<T: Serialize + 'static>(e: &Self::Trait) -> &dyn erased_serde::Serialize {
+        Self::storage_ref::<T>(e)
+    }
+
+    fn erased_deserialize<T: DeserializeOwned + 'static>(
+        trait_accessor: TraitAccessor<T, D>,
+        deserializer: &mut dyn erased_serde::Deserializer<'_>,
+    ) -> Result<Box<Self::Trait>, erased_serde::Error> {
+        let data = erased_serde::deserialize(deserializer)?;
+        Ok(Box::new(OrderedStorage::from_entries(trait_accessor, data)))
+    }
+}
+
+/* ==================== Type registry ==================== */
+
+type SerializeFn<S> = fn(&S) -> &dyn erased_serde::Serialize;
+type DeserializeFn<Dyn, F, H> = fn(
+    &mut TraitTypeMap<Dyn, F, H>,
+    &mut dyn erased_serde::Deserializer<'_>,
+) -> Result<(), erased_serde::Error>;
+
+struct SerdeRegistration<Dyn: ?Sized + 'static, F: SerdeFamily<Dyn>, H> {
+    type_id: TypeId,
+    serialize: SerializeFn<F::Trait>,
+    deserialize: DeserializeFn<Dyn, F, H>,
+}
+
+fn deserialize_storage<Dyn, F, H, T>(
+    map: &mut TraitTypeMap<Dyn, F, H>,
+    deserializer: &mut dyn erased_serde::Deserializer<'_>,
+    clone_storage: Option<CloneStorageFn<F::Trait>>,
+) -> Result<(), erased_serde::Error>
+where
+    Dyn: ?Sized + 'static,
+    F: SerdeFamily<Dyn>,
+    H: BuildHasher,
+    T: 'static + TraitAccessible<Dyn> + DeserializeOwned,
+{
+    let storage = F::erased_deserialize::<T>(T::get_accessor(), deserializer)?;
+    // Duplicate tags are rejected before deserializing, so this always inserts
+    map.insert_storage::<T>(storage, clone_storage);
+    Ok(())
+}
+
+fn deserialize_storage_fn<Dyn, F, H, T>(
+    map: &mut TraitTypeMap<Dyn, F, H>,
+    deserializer: &mut dyn erased_serde::Deserializer<'_>,
+) -> Result<(), erased_serde::Error>
+where
+    Dyn: ?Sized + 'static,
+    F: SerdeFamily<Dyn>,
+    H: BuildHasher,
+    T: 'static + TraitAccessible<Dyn> + DeserializeOwned,
+{
+    deserialize_storage::<Dyn, F, H, T>(map, deserializer, None)
+}
+
+fn deserialize_cloneable_storage_fn<Dyn, F, H, T>(
+    map: &mut TraitTypeMap<Dyn, F, H>,
+    deserializer: &mut dyn erased_serde::Deserializer<'_>,
+) -> Result<(), erased_serde::Error>
+where
+    Dyn: ?Sized + 'static,
+    F: SerdeFamily<Dyn>,
+    H: BuildHasher,
+    T: 'static + Clone + TraitAccessible<Dyn> + DeserializeOwned,
+{
+    deserialize_storage::<Dyn, F, H, T>(map, deserializer, Some(F::clone_storage::<T>))
+}
+
+/// Table of stable string tags used to serialize and deserialize a [`TraitTypeMap`].
+///
+/// A map is serialized as `{tag: storage}`, where each storage is serialized by its family
+/// (a sequence of values for `VecFamily`, `{slots, generations}` for `VecOptionFamily` so holes
+/// keep their indices and handles stay valid, the same laid out by key for `SparseSetFamily`,
+/// an optional value for `OptionFamily`,
+/// a map from keys to values for `HashMapFamily`, and a sequence of `(priority, value)` pairs
+/// for `OrderedFamily`).
+///
+/// # Examples
+///
+/// ```rust
+/// use serde::{Deserialize, Serialize};
+/// use trait_type_map::{impl_trait_accessible, SerdeRegistry, TraitTypeMap, VecOptionFamily};
+///
+/// trait Component {}
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Position(f32, f32);
+/// impl Component for Position {}
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Health(u32);
+/// impl Component for Health {}
+///
+/// impl_trait_accessible!(dyn Component; Position, Health);
+///
+/// let mut registry = SerdeRegistry::<dyn Component, VecOptionFamily>::new();
+/// registry.register::<Position>("position");
+/// registry.register::<Health>("health");
+///
+/// let mut map: TraitTypeMap<dyn Component, VecOptionFamily> = TraitTypeMap::new();
+/// map.register_type_storage::<Position>();
+/// map.register_type_storage::<Health>();
+/// map.get_storage_mut::<Position>().push(Position(1.0, 2.0));
+/// let hole = map.get_storage_mut::<Health>().push(Health(10));
+/// let kept = map.get_storage_mut::<Health>().push(Health(20));
+/// map.get_storage_mut::<Health>().take(hole);
+/// // Reuses the vacated slot under a new generation
+/// let reused = map.get_storage_mut::<Health>().insert(Health(30));
+/// assert_eq!(reused.index(), hole);
+///
+/// let json = serde_json::to_string(&registry.serializable(&map)).unwrap();
+///
+/// let mut de = serde_json::Deserializer::from_str(&json);
+/// let loaded = registry.deserialize_map(&mut de).unwrap();
+/// assert_eq!(loaded.get_storage::<Health>().get(kept).unwrap().0, 20);
+/// assert_eq!(loaded.get_storage::<Health>().get_by_handle(reused).unwrap().0, 30);
+///
+/// // Unknown tags are reported as errors
+/// let mut de = serde_json::Deserializer::from_str(r#"{"velocity": []}"#);
+/// assert!(registry.deserialize_map(&mut de).is_err());
+/// ```
+pub struct SerdeRegistry<Dyn: ?Sized + 'static, F: SerdeFamily<Dyn>, H = DefaultHashBuilder> {
+    by_tag: HashMap<&'static str, SerdeRegistration<Dyn, F, H>>,
+    tags: HashMap<TypeId, &'static str>,
+}
+
+impl<Dyn: ?Sized + 'static, F: SerdeFamily<Dyn>, H: BuildHasher + Default> Default
+    for SerdeRegistry<Dyn, F, H>
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// `H` is the hash builder of the maps the registry serializes and deserializes.
+impl<Dyn: ?Sized + 'static, F: SerdeFamily<Dyn>, H: BuildHasher + Default>
+    SerdeRegistry<Dyn, F, H>
+{
+    pub fn new() -> Self {
+        Self {
+            by_tag: HashMap::default(),
+            tags: HashMap::default(),
+        }
+    }
+
+    /// Register `T` under `tag`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `T` or `tag` is already registered.
+    pub fn register<T>(&mut self, tag: &'static str) -> &mut Self
+    where
+        T: 'static + TraitAccessible<Dyn> + Serialize + DeserializeOwned,
+    {
+        self.insert::<T>(tag, deserialize_storage_fn::<Dyn, F, H, T>)
+    }
+
+    /// Register `T` under `tag`, so that its storage stays cloneable after deserialization.
+    ///
+    /// See [`TraitTypeMap::register_cloneable_type_storage`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `T` or `tag` is already registered.
+    pub fn register_cloneable<T>(&mut self, tag: &'static str) -> &mut Self
+    where
+        T: 'static + Clone + TraitAccessible<Dyn> + Serialize + DeserializeOwned,
+    {
+        self.insert::<T>(tag, deserialize_cloneable_storage_fn::<Dyn, F, H, T>)
+    }
+
+    fn insert<T>(&mut self, tag: &'static str, deserialize: DeserializeFn<Dyn, F, H>) -> &mut Self
+    where
+        T: 'static + Serialize,
+    {
+        let type_id = TypeId::of::<T>();
+        assert!(
+            !self.tags.contains_key(&type_id),
+            "type already registered: {}",
+            type_name::<T>()
+        );
+        assert!(
+            !self.by_tag.contains_key(tag),
+            "tag already registered: {tag}"
+        );
+        self.tags.insert(type_id, tag);
+        self.by_tag.insert(
+            tag,
+            SerdeRegistration {
+                type_id,
+                serialize: F::erased_serialize::<T>,
+                deserialize,
+            },
+        );
+        self
+    }
+
+    /// Tag registered for the type with the given `TypeId`.
+    pub fn tag_of(&self, id: TypeId) -> Option<&'static str> {
+        self.tags.get(&id).copied()
+    }
+
+    /// Wrap `map` so it can be passed to any serde serializer.
+    pub fn serializable<'a>(
+        &'a self,
+        map: &'a TraitTypeMap<Dyn, F, H>,
+    ) -> SerializableMap<'a, Dyn, F, H> {
+        SerializableMap {
+            registry: self,
+            map,
+        }
+    }
+
+    /// Serialize `map` as `{tag: storage}`.
+    ///
+    /// Fails if a registered type of the map has no tag in this registry.
+    pub fn serialize_map<S: Serializer>(
+        &self,
+        map: &TraitTypeMap<Dyn, F, H>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let mut out = serializer.serialize_map(None)?;
+        for (id, name) in map.registered_types() {
+            let tag = self.tag_of(id).ok_or_else(|| {
+                ser::Error::custom(format_args!("no serde tag registered for {name}"))
+            })?;
+            let storage = map
+                .get_trait_storage(id)
+                .expect("registered type has a storage");
+            out.serialize_entry(tag, (self.by_tag[tag].serialize)(storage))?;
+        }
+        out.end()
+    }
+
+    /// Deserialize a map previously serialized with [`serialize_map`](Self::serialize_map).
+    ///
+    /// Fails on tags that are not registered and on tags that appear more than once.
+    pub fn deserialize_map<'de, D: Deserializer<'de>>(
+        &self,
+        deserializer: D,
+    ) -> Result<TraitTypeMap<Dyn, F, H>, D::Error> {
+        deserializer.deserialize_map(MapVisitor { registry: self })
+    }
+}
+
+/// A [`TraitTypeMap`] paired with the [`SerdeRegistry`] used to serialize it.
+///
+/// Created by [`SerdeRegistry::serializable`].
+pub struct SerializableMap<'a, Dyn: ?Sized + 'static, F: SerdeFamily<Dyn>, H = DefaultHashBuilder> {
+    registry: &'a SerdeRegistry<Dyn, F, H>,
+    map: &'a TraitTypeMap<Dyn, F, H>,
+}
+
+impl<Dyn: ?Sized + 'static, F: SerdeFamily<Dyn>, H: BuildHasher + Default> Serialize
+    for SerializableMap<'_, Dyn, F, H>
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.registry.serialize_map(self.map, serializer)
+    }
+}
+
+impl<'de, Dyn: ?Sized + 'static, F: SerdeFamily<Dyn>, H: BuildHasher + Default> DeserializeSeed<'de>
+    for &SerdeRegistry<Dyn, F, H>
+{
+    type Value = TraitTypeMap<Dyn, F, H>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        self.deserialize_map(deserializer)
+    }
+}
+
+struct MapVisitor<'a, Dyn: ?Sized + 'static, F: SerdeFamily<Dyn>, H> {
+    registry: &'a SerdeRegistry<Dyn, F, H>,
+}
+
+impl<'de, Dyn: ?Sized + 'static, F: SerdeFamily<Dyn>, H: BuildHasher + Default> Visitor<'de>
+    for MapVisitor<'_, Dyn, F, H>
+{
+    type Value = TraitTypeMap<Dyn, F, H>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a map of type tags to storages")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Self::Value, A::Error> {
+        let mut map =
+            TraitTypeMap::with_capacity_and_hasher(access.size_hint().unwrap_or(0), H::default());
+        while let Some(tag) = access.next_key::<String>()? {
+            let registration = self
+                .registry
+                .by_tag
+                .get(tag.as_str())
+                .ok_or_else(|| de::Error::custom(format_args!("unknown type tag `{tag}`")))?;
+            if map.contains_type_id(registration.type_id) {
+                return Err(de::Error::custom(format_args!(
+                    "duplicate type tag `{tag}`"
+                )));
+            }
+            access.next_value_seed(StorageSeed {
+                registration,
+                map: &mut map,
+            })?;
+        }
+        Ok(map)
+    }
+}
+
+struct StorageSeed<'a, Dyn: ?Sized + 'static, F: SerdeFamily<Dyn>, H> {
+    registration: &'a SerdeRegistration<Dyn, F, H>,
+    map: &'a mut TraitTypeMap<Dyn, F, H>,
+}
+
+impl<'de, Dyn: ?Sized + 'static, F: SerdeFamily<Dyn>, H: BuildHasher> DeserializeSeed<'de>
+    for StorageSeed<'_, Dyn, F, H>
+{
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        let mut erased = <dyn erased_serde::Deserializer>::erase(deserializer);
+        (self.registration.deserialize)(self.map, &mut erased).map_err(de::Error::custom)
+    }
+}
@@ -0,0 +1,368 @@
+use crate::{DefaultHashBuilder, SyncStorageFamily, TraitAccessible, TraitTypeMapError};
+use indexmap::map::Entry;
+use indexmap::IndexMap;
+use std::any::{type_name, TypeId};
+use std::fmt;
+use std::hash::BuildHasher;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+use std::sync::{PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard, TryLockError};
+
+/// A thread-safe [`TraitTypeMap`](crate::TraitTypeMap) where each type's storage sits behind its own `RwLock`.
+///
+/// Threads can hold a write guard for one type and read guards for others at the same time.
+/// Only `Send + Sync` types can be registered.
+///
+/// Lock poisoning is ignored: a storage stays usable after a thread panics while holding its guard.
+/// Like `TraitTypeMap`, the map hashes `TypeId`s with `S`, see [`with_hasher`](Self::with_hasher).
+///
+/// # Examples
+///
+/// ```rust
+/// use std::thread;
+/// use trait_type_map::{impl_trait_accessible, SyncTraitTypeMap, VecFamily};
+///
+/// trait Body {
+///     fn mass(&self) -> f32;
+/// }
+///
+/// struct Planet(f32);
+/// impl Body for Planet {
+///     fn mass(&self) -> f32 { self.0 }
+/// }
+///
+/// struct Moon(f32);
+/// impl Body for Moon {
+///     fn mass(&self) -> f32 { self.0 }
+/// }
+///
+/// impl_trait_accessible!(dyn Body; Planet, Moon);
+///
+/// let mut map: SyncTraitTypeMap<dyn Body, VecFamily> = SyncTraitTypeMap::new();
+/// map.register_type_storage::<Planet>();
+/// map.register_type_storage::<Moon>();
+/// map.get_storage_mut::<Moon>().push(Moon(1.0));
+///
+/// thread::scope(|s| {
+///     s.spawn(|| map.write::<Planet>().push(Planet(80.0)));
+///     s.spawn(|| assert_eq!(map.read::<Moon>().get(0).mass(), 1.0));
+/// });
+///
+/// assert_eq!(map.read::<Planet>().data.len(), 1);
+/// ```
+pub struct SyncTraitTypeMap<
+    Dyn: ?Sized + 'static,
+    F: SyncStorageFamily<Dyn>,
+    S = DefaultHashBuilder,
+> {
+    /// Registered storages, in registration order.
+    entries: IndexMap<TypeId, LockedStorage<F::SyncTrait>, S>,
+}
+
+/// A registered storage behind its lock, along with the name of the type it stores.
+struct LockedStorage<S: ?Sized> {
+    type_name: &'static str,
+    storage: RwLock<Box<S>>,
+}
+
+impl<Dyn: ?Sized + 'static, F: SyncStorageFamily<Dyn>, S: BuildHasher + Default> Default
+    for SyncTraitTypeMap<Dyn, F, S>
+{
+    fn default() -> Self {
+        Self::with_hasher(S::default())
+    }
+}
+
+impl<Dyn: ?Sized + 'static, F: SyncStorageFamily<Dyn>, S: BuildHasher> fmt::Debug
+    for SyncTraitTypeMap<Dyn, F, S>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SyncTraitTypeMap")
+            .field(
+                "types",
+                &self
+                    .registered_types()
+                    .map(|(_, name)| name)
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+impl<Dyn: ?Sized + 'static, F: SyncStorageFamily<Dyn>> SyncTraitTypeMap<Dyn, F> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new map with pre-allocated capacity for the given number of types.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_and_hasher(capacity, DefaultHashBuilder::default())
+    }
+}
+
+impl<Dyn: ?Sized + 'static, F: SyncStorageFamily<Dyn>, S: BuildHasher> SyncTraitTypeMap<Dyn, F, S> {
+    /// Create a new map that hashes `TypeId`s with `hash_builder`, e.g. [`TypeIdBuildHasher`](crate::TypeIdBuildHasher).
+    pub fn with_hasher(hash_builder: S) -> Self {
+        Self::with_capacity_and_hasher(0, hash_builder)
+    }
+
+    /// Create a new map with pre-allocated capacity that hashes `TypeId`s with `hash_builder`.
+    pub fn with_capacity_and_hasher(capacity: usize, hash_builder: S) -> Self {
+        Self {
+            entries: IndexMap::with_capacity_and_hasher(capacity, hash_builder),
+        }
+    }
+
+    /// Register a storage for `T`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `T` is already registered. See [`try_register_type_storage`](Self::try_register_type_storage).
+    pub fn register_type_storage<T>(&mut self)
+    where
+        T: 'static + Send + Sync + TraitAccessible<Dyn>,
+    {
+        if let Err(err) = self.try_register_type_storage::<T>() {
+            panic!("{err}");
+        }
+    }
+
+    /// Register a storage for `T`, failing with [`TraitTypeMapError::AlreadyRegistered`]
+    /// instead of panicking if it already exists.
+    pub fn try_register_type_storage<T>(&mut self) -> Result<(), TraitTypeMapError>
+    where
+        T: 'static + Send + Sync + TraitAccessible<Dyn>,
+    {
+        match self.entries.entry(TypeId::of::<T>()) {
+            Entry::Occupied(_) => Err(TraitTypeMapError::AlreadyRegistered {
+                type_name: type_name::<T>(),
+            }),
+            Entry::Vacant(slot) => {
+                slot.insert(LockedStorage {
+                    type_name: type_name::<T>(),
+                    storage: RwLock::new(F::make_sync::<T>(T::get_accessor())),
+                });
+                Ok(())
+            }
+        }
+    }
+
+    /// Remove the storage for `T`, returning it if `T` was registered.
+    pub fn unregister_type_storage<T>(&mut self) -> Option<Box<F::SyncTrait>>
+    where
+        T: 'static,
+    {
+        self.entries.shift_remove(&TypeId::of::<T>()).map(|e| {
+            e.storage
+                .into_inner()
+                .unwrap_or_else(PoisonError::into_inner)
+        })
+    }
+
+    /// Whether a storage is registered for `T`.
+    #[inline(always)]
+    pub fn contains_type<T>(&self) -> bool
+    where
+        T: 'static,
+    {
+        self.contains_type_id(TypeId::of::<T>())
+    }
+
+    /// Whether a storage is registered under `id`.
+    #[inline(always)]
+    pub fn contains_type_id(&self, id: TypeId) -> bool {
+        self.entries.contains_key(&id)
+    }
+
+    /// Iterate over the `TypeId` and type name of every registered type, in registration order.
+    pub fn registered_types(&self) -> impl Iterator<Item = (TypeId, &'static str)> + '_ {
+        self.entries.iter().map(|(&id, e)| (id, e.type_name))
+    }
+
+    /// Lock the storage for `T` for reading, blocking until no writer holds it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `T` is not registered.
+    pub fn read<T>(&self) -> StorageReadGuard<'_, Dyn, F, T>
+    where
+        T: 'static,
+    {
+        let e = self.entry::<T>().unwrap_or_else(|err| panic!("{err}"));
+        let guard = e.storage.read().unwrap_or_else(PoisonError::into_inner);
+        StorageReadGuard::new(guard).unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Lock the storage for `T` for writing, blocking until no other guard holds it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `T` is not registered.
+    pub fn write<T>(&self) -> StorageWriteGuard<'_, Dyn, F, T>
+    where
+        T: 'static,
+    {
+        let e = self.entry::<T>().unwrap_or_else(|err| panic!("{err}"));
+        let guard = e.storage.write().unwrap_or_else(PoisonError::into_inner);
+        StorageWriteGuard::new(guard).unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Lock the storage for `T` for reading without blocking.
+    ///
+    /// Fails with [`TraitTypeMapError::StorageLocked`] if a writer holds the storage.
+    pub fn try_read<T>(&self) -> Result<StorageReadGuard<'_, Dyn, F, T>, TraitTypeMapError>
+    where
+        T: 'static,
+    {
+        let e = self.entry::<T>()?;
+        let guard = match e.storage.try_read() {
+            Ok(guard) => guard,
+            Err(TryLockError::Poisoned(poisoned)) => poisoned.into_inner(),
+            Err(TryLockError::WouldBlock) => {
+                return Err(TraitTypeMapError::StorageLocked {
+                    type_name: e.type_name,
+                })
+            }
+        };
+        StorageReadGuard::new(guard)
+    }
+
+    /// Lock the storage for `T` for writing without blocking.
+    ///
+    /// Fails with [`TraitTypeMapError::StorageLocked`] if any other guard holds the storage.
+    pub fn try_write<T>(&self) -> Result<StorageWriteGuard<'_, Dyn, F, T>, TraitTypeMapError>
+    where
+        T: 'static,
+    {
+        let e = self.entry::<T>()?;
+        let guard = match e.storage.try_write() {
+            Ok(guard) => guard,
+            Err(TryLockError::Poisoned(poisoned)) => poisoned.into_inner(),
+            Err(TryLockError::WouldBlock) => {
+                return Err(TraitTypeMapError::StorageLocked {
+                    type_name: e.type_name,
+                })
+            }
+        };
+        StorageWriteGuard::new(guard)
+    }
+
+    /// Get mutable access to the storage for `T` without locking, as the map is borrowed exclusively.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `T` is not registered.
+    #[inline(always)]
+    pub fn get_storage_mut<T>(&mut self) -> &mut F::Storage<T>
+    where
+        T: 'static,
+    {
+        self.try_get_storage_mut::<T>()
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    #[inline(always)]
+    pub fn try_get_storage_mut<T>(&mut self) -> Result<&mut F::Storage<T>, TraitTypeMapError>
+    where
+        T: 'static,
+    {
+        let e =
+            self.entries
+                .get_mut(&TypeId::of::<T>())
+                .ok_or(TraitTypeMapError::NotRegistered {
+                    type_name: type_name::<T>(),
+                })?;
+        let storage = e.storage.get_mut().unwrap_or_else(PoisonError::into_inner);
+        F::try_storage_mut::<T>(F::as_trait_mut(&mut **storage))
+    }
+
+    /// Mutable access to every storage without locking, as the map is borrowed exclusively.
+    #[cfg_attr(not(feature = "rayon"), allow(dead_code))]
+    pub(crate) fn storages_mut(&mut self) -> impl Iterator<Item = (TypeId, &mut F::SyncTrait)> {
+        self.entries.iter_mut().map(|(&id, e)| {
+            let storage = e.storage.get_mut().unwrap_or_else(PoisonError::into_inner);
+            (id, &mut **storage)
+        })
+    }
+
+    fn entry<T: 'static>(&self) -> Result<&LockedStorage<F::SyncTrait>, TraitTypeMapError> {
+        self.entries
+            .get(&TypeId::of::<T>())
+            .ok_or(TraitTypeMapError::NotRegistered {
+                type_name: type_name::<T>(),
+            })
+    }
+}
+
+/// Shared access to the storage of type `T` in a [`SyncTraitTypeMap`].
+///
+/// Created by [`SyncTraitTypeMap::read`] and [`SyncTraitTypeMap::try_read`].
+pub struct StorageReadGuard<'a, Dyn: ?Sized + 'static, F: SyncStorageFamily<Dyn>, T> {
+    guard: RwLockReadGuard<'a, Box<F::SyncTrait>>,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<'a, Dyn: ?Sized + 'static, F: SyncStorageFamily<Dyn>, T: 'static>
+    StorageReadGuard<'a, Dyn, F, T>
+{
+    fn new(guard: RwLockReadGuard<'a, Box<F::SyncTrait>>) -> Result<Self, TraitTypeMapError> {
+        // Checked once here so that `deref` cannot fail
+        F::try_storage_ref::<T>(F::as_trait(&**guard))?;
+        Ok(Self {
+            guard,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Dyn: ?Sized + 'static, F: SyncStorageFamily<Dyn>, T: 'static> Deref
+    for StorageReadGuard<'_, Dyn, F, T>
+{
+    type Target = F::Storage<T>;
+
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target {
+        F::storage_ref::<T>(F::as_trait(&**self.guard))
+    }
+}
+
+/// Exclusive access to the storage of type `T` in a [`SyncTraitTypeMap`].
+///
+/// Created by [`SyncTraitTypeMap::write`] and [`SyncTraitTypeMap::try_write`].
+pub struct StorageWriteGuard<'a, Dyn: ?Sized + 'static, F: SyncStorageFamily<Dyn>, T> {
+    guard: RwLockWriteGuard<'a, Box<F::SyncTrait>>,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<'a, Dyn: ?Sized + 'static, F: SyncStorageFamily<Dyn>, T: 'static>
+    StorageWriteGuard<'a, Dyn, F, T>
+{
+    fn new(guard: RwLockWriteGuard<'a, Box<F::SyncTrait>>) -> Result<Self, TraitTypeMapError> {
+        // Checked once here so that `deref` cannot fail
+        F::try_storage_ref::<T>(F::as_trait(&**guard))?;
+        Ok(Self {
+            guard,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<Dyn: ?Sized + 'static, F: SyncStorageFamily<Dyn>, T: 'static> Deref
+    for StorageWriteGuard<'_, Dyn, F, T>
+{
+    type Target = F::Storage<T>;
+
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target {
+        F::storage_ref::<T>(F::as_trait(&**self.guard))
+    }
+}
+
+impl<Dyn: ?Sized + 'static, F: SyncStorageFamily<Dyn>, T: 'static> DerefMut
+    for StorageWriteGuard<'_, Dyn, F, T>
+{
+    #[inline(always)]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        F::storage_mut::<T>(F::as_trait_mut(&mut **self.guard))
+    }
+}
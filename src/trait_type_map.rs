@@ -1,5 +1,16 @@
-use ahash::AHashMap;
-use std::any::{Any, TypeId};
+use crate::hasher::HashMap;
+use crate::{DefaultHashBuilder, TraitTypeMapError};
+use dyn_clone::DynClone;
+use indexmap::map::Entry;
+use indexmap::IndexMap;
+use std::any::{type_name, Any, TypeId};
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::fmt;
+use std::hash::{BuildHasher, Hash, Hasher};
+use std::marker::PhantomData;
+use std::sync::Arc;
 
 /// Accessor functions for converting a concrete type to a trait object.
 ///
@@ -12,6 +23,14 @@ pub struct TraitAccessor<T, Dyn: ?Sized> {
     pub up_box: fn(T) -> Box<Dyn>,
 }
 
+impl<T, Dyn: ?Sized> Clone for TraitAccessor<T, Dyn> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T, Dyn: ?Sized> Copy for TraitAccessor<T, Dyn> {}
+
 /// Macro for implementing `TraitAccessible` for types.
 ///
 /// This macro generates the necessary implementation to make types accessible
@@ -29,17 +48,124 @@ pub struct TraitAccessor<T, Dyn: ?Sized> {
 ///
 /// impl_trait_accessible!(dyn MyTrait; TypeA, TypeB);
 /// ```
+///
+/// Trait objects may carry auto-trait bounds, and a single generic type can be given
+/// its own generic parameters (lifetimes, bounded types, or consts) and where clause:
+///
+/// ```rust
+/// # use trait_type_map::impl_trait_accessible;
+/// # use std::fmt::Debug;
+/// trait Shape {}
+/// struct Square;
+/// struct Wrapper<T>(T);
+/// struct Pair<A, B>(A, B);
+/// struct Grid<const N: usize>([u8; N]);
+/// struct Named<'a, T>(&'a str, T);
+/// impl Shape for Square {}
+/// impl<T: Debug> Shape for Wrapper<T> {}
+/// impl<A, B> Shape for Pair<A, B> {}
+/// impl<const N: usize> Shape for Grid<N> {}
+/// impl<T> Shape for Named<'_, T> {}
+///
+/// impl_trait_accessible!(dyn Shape + Send + Sync; Square);
+/// impl_trait_accessible!(impl<T: Debug + 'static> dyn Shape; Wrapper<T>);
+/// impl_trait_accessible!(impl<A, B> dyn Shape; Pair<A, B> where A: Into<u32> + 'static, B: 'static);
+/// impl_trait_accessible!(impl<const N: usize> dyn Shape; Grid<N>);
+/// impl_trait_accessible!(impl<T: Into<Vec<u8>> + 'static> dyn Shape; Named<'static, T>);
+/// ```
 #[macro_export]
 macro_rules! impl_trait_accessible {
-    (dyn $dyn:path; $($ty:ty),+ $(,)?) => {$(
-        impl $crate::TraitAccessible<dyn $dyn> for $ty {
-            fn get_accessor() -> $crate::TraitAccessor<Self, dyn $dyn> {
+    // Collect the generic parameters up to the `>` that closes them, tracking nested `<`s in the
+    // second list (`>>` is a single token, so it closes two levels at once)
+    (@generics [$($g:tt)*] [] > $($rest:tt)*) => {
+        $crate::impl_trait_accessible!(@impl [$($g)*] $($rest)*);
+    };
+    (@generics [$($g:tt)*] [+] >> $($rest:tt)*) => {
+        $crate::impl_trait_accessible!(@impl [$($g)* >] $($rest)*);
+    };
+    (@generics [$($g:tt)*] [+ + $($d:tt)*] >> $($rest:tt)*) => {
+        $crate::impl_trait_accessible!(@generics [$($g)* >>] [$($d)*] $($rest)*);
+    };
+    (@generics [$($g:tt)*] [+ $($d:tt)*] > $($rest:tt)*) => {
+        $crate::impl_trait_accessible!(@generics [$($g)* >] [$($d)*] $($rest)*);
+    };
+    (@generics [$($g:tt)*] [$($d:tt)*] < $($rest:tt)*) => {
+        $crate::impl_trait_accessible!(@generics [$($g)* <] [+ $($d)*] $($rest)*);
+    };
+    (@generics [$($g:tt)*] [$($d:tt)*] $t:tt $($rest:tt)*) => {
+        $crate::impl_trait_accessible!(@generics [$($g)* $t] [$($d)*] $($rest)*);
+    };
+    (@impl [$($g:tt)*] $dyn:ty; $ty:ty $(where $($wc:tt)+)?) => {
+        impl<$($g)*> $crate::TraitAccessible<$dyn> for $ty
+        where
+            $ty: 'static,
+            $($($wc)+)?
+        {
+            fn get_accessor() -> $crate::TraitAccessor<Self, $dyn> {
+                $crate::TraitAccessor { up_ref: |v| v, up_mut: |v| v, up_box: |v| Box::new(v) }
+            }
+        }
+    };
+    (impl < $($rest:tt)+) => {
+        $crate::impl_trait_accessible!(@generics [] [] $($rest)+);
+    };
+    ($dyn:ty; $($ty:ty),+ $(,)?) => {$(
+        impl $crate::TraitAccessible<$dyn> for $ty {
+            fn get_accessor() -> $crate::TraitAccessor<Self, $dyn> {
                 $crate::TraitAccessor { up_ref: |v| v, up_mut: |v| v, up_box: |v| Box::new(v) }
             }
         }
     )+};
 }
 
+/* ==================== Observer hooks ==================== */
+
+/// Callback receiving the index and a reference to a value inserted into or removed from a storage.
+pub type Hook<T> = Arc<dyn Fn(usize, &T) + Send + Sync>;
+
+/// Insert and remove callbacks of a storage, for the concrete type and for the trait object.
+///
+/// Hooks are not carried over when a storage is cloned; the clone starts without any.
+struct StorageHooks<T, Dyn: ?Sized> {
+    insert: Vec<Hook<T>>,
+    remove: Vec<Hook<T>>,
+    insert_dyn: Vec<Hook<Dyn>>,
+    remove_dyn: Vec<Hook<Dyn>>,
+}
+
+impl<T, Dyn: ?Sized> Default for StorageHooks<T, Dyn> {
+    fn default() -> Self {
+        Self {
+            insert: Vec::new(),
+            remove: Vec::new(),
+            insert_dyn: Vec::new(),
+            remove_dyn: Vec::new(),
+        }
+    }
+}
+
+impl<T, Dyn: ?Sized> StorageHooks<T, Dyn> {
+    #[inline(always)]
+    fn inserted(&self, i: usize, v: &T, up_ref: fn(&T) -> &Dyn) {
+        Self::fire(&self.insert, &self.insert_dyn, i, v, up_ref);
+    }
+
+    #[inline(always)]
+    fn removed(&self, i: usize, v: &T, up_ref: fn(&T) -> &Dyn) {
+        Self::fire(&self.remove, &self.remove_dyn, i, v, up_ref);
+    }
+
+    #[inline(always)]
+    fn fire(hooks: &[Hook<T>], dyn_hooks: &[Hook<Dyn>], i: usize, v: &T, up_ref: fn(&T) -> &Dyn) {
+        for hook in hooks {
+            hook(i, v);
+        }
+        for hook in dyn_hooks {
+            hook(i, up_ref(v));
+        }
+    }
+}
+
 /* ==================== Vector backend ==================== */
 
 /// Storage for multiple values of a single type in a vector.
@@ -48,18 +174,49 @@ macro_rules! impl_trait_accessible {
 pub struct VecStorage<T, Dyn: ?Sized> {
     pub data: Vec<T>,
     trait_accessor: TraitAccessor<T, Dyn>,
+    hooks: StorageHooks<T, Dyn>,
+}
+impl<T: Clone, Dyn: ?Sized> Clone for VecStorage<T, Dyn> {
+    fn clone(&self) -> Self {
+        Self {
+            data: self.data.clone(),
+            trait_accessor: self.trait_accessor,
+            hooks: StorageHooks::default(),
+        }
+    }
 }
+
+impl<T, Dyn: ?Sized + DynClone> VecStorage<T, Dyn> {
+    /// Clone the value at `i` into a new boxed trait object, leaving the stored value in place.
+    pub fn clone_boxed(&self, i: usize) -> Box<Dyn> {
+        dyn_clone::clone_box(self.get_dyn(i))
+    }
+}
+
 impl<T, Dyn: ?Sized> VecStorage<T, Dyn> {
     pub fn new(trait_accessor: TraitAccessor<T, Dyn>) -> Self {
         Self {
             data: Vec::new(),
             trait_accessor,
+            hooks: StorageHooks::default(),
         }
     }
 
+    /// Call `hook` with the index and value of every value pushed from now on.
+    pub fn on_insert(&mut self, hook: impl Fn(usize, &T) + Send + Sync + 'static) {
+        self.hooks.insert.push(Arc::new(hook));
+    }
+
+    /// Call `hook` with the index and value of every value removed from now on, before it is returned.
+    pub fn on_remove(&mut self, hook: impl Fn(usize, &T) + Send + Sync + 'static) {
+        self.hooks.remove.push(Arc::new(hook));
+    }
+
     pub fn push(&mut self, v: T) -> usize {
         let idx = self.data.len();
         self.data.push(v);
+        self.hooks
+            .inserted(idx, &self.data[idx], self.trait_accessor.up_ref);
         idx
     }
 
@@ -67,6 +224,31 @@ impl<T, Dyn: ?Sized> VecStorage<T, Dyn> {
         self.data.iter()
     }
 
+    /// Iterate over all stored values along with their indices.
+    pub fn iter_indexed(&self) -> impl Iterator<Item = (usize, &T)> {
+        self.data.iter().enumerate()
+    }
+
+    pub fn iter_indexed_mut(&mut self) -> impl Iterator<Item = (usize, &mut T)> {
+        self.data.iter_mut().enumerate()
+    }
+
+    /// Iterate over all stored values as trait objects, along with their indices.
+    pub fn iter_dyn(&self) -> impl Iterator<Item = (usize, &Dyn)> {
+        let up_ref = self.trait_accessor.up_ref;
+        self.iter_indexed().map(move |(i, v)| (i, up_ref(v)))
+    }
+
+    pub fn iter_dyn_mut(&mut self) -> impl Iterator<Item = (usize, &mut Dyn)> {
+        let up_mut = self.trait_accessor.up_mut;
+        self.iter_indexed_mut().map(move |(i, v)| (i, up_mut(v)))
+    }
+
+    /// Indices of all stored values, `0..len`.
+    pub fn indices(&self) -> std::ops::Range<usize> {
+        0..self.data.len()
+    }
+
     pub fn get(&self, i: usize) -> &T {
         self.data.get(i).unwrap()
     }
@@ -84,12 +266,81 @@ impl<T, Dyn: ?Sized> VecStorage<T, Dyn> {
         up_mut(self.get_mut(i))
     }
 
+    /// Remove the value at `i`, moving the last value into its place.
+    ///
+    /// This invalidates the index of the last value; use
+    /// [`swap_remove_tracked`](Self::swap_remove_tracked) to find out which index moved.
     pub fn swap_remove(&mut self, i: usize) -> T {
-        self.data.swap_remove(i)
+        self.swap_remove_tracked(i).0
+    }
+
+    /// Remove the value at `i`, moving the last value into its place.
+    ///
+    /// Returns the removed value and, if a value was moved, the index it was moved from
+    /// (it now lives at `i`).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` is out of bounds.
+    pub fn swap_remove_tracked(&mut self, i: usize) -> (T, Option<usize>) {
+        let last = self.data.len().wrapping_sub(1);
+        let removed = self.data.swap_remove(i);
+        self.hooks.removed(i, &removed, self.trait_accessor.up_ref);
+        (removed, (i != last).then_some(last))
     }
 
     pub fn take_boxed(&mut self, i: usize) -> Box<Dyn> {
-        (self.trait_accessor.up_box)(self.data.swap_remove(i))
+        let removed = self.swap_remove(i);
+        (self.trait_accessor.up_box)(removed)
+    }
+
+    /// Checked counterpart of [`get`](Self::get).
+    pub fn try_get(&self, i: usize) -> Result<&T, TraitTypeMapError> {
+        let len = self.data.len();
+        self.data
+            .get(i)
+            .ok_or(TraitTypeMapError::IndexOutOfBounds { index: i, len })
+    }
+
+    /// Checked counterpart of [`get_mut`](Self::get_mut).
+    pub fn try_get_mut(&mut self, i: usize) -> Result<&mut T, TraitTypeMapError> {
+        let len = self.data.len();
+        self.data
+            .get_mut(i)
+            .ok_or(TraitTypeMapError::IndexOutOfBounds { index: i, len })
+    }
+
+    /// Checked counterpart of [`get_dyn`](Self::get_dyn).
+    pub fn try_get_dyn(&self, i: usize) -> Result<&Dyn, TraitTypeMapError> {
+        self.try_get(i).map(|v| (self.trait_accessor.up_ref)(v))
+    }
+
+    /// Checked counterpart of [`get_dyn_mut`](Self::get_dyn_mut).
+    pub fn try_get_dyn_mut(&mut self, i: usize) -> Result<&mut Dyn, TraitTypeMapError> {
+        let up_mut = self.trait_accessor.up_mut;
+        self.try_get_mut(i).map(up_mut)
+    }
+
+    /// Checked counterpart of [`swap_remove`](Self::swap_remove).
+    pub fn try_swap_remove(&mut self, i: usize) -> Result<T, TraitTypeMapError> {
+        self.check_index(i)?;
+        Ok(self.swap_remove(i))
+    }
+
+    /// Checked counterpart of [`take_boxed`](Self::take_boxed).
+    pub fn try_take_boxed(&mut self, i: usize) -> Result<Box<Dyn>, TraitTypeMapError> {
+        self.try_swap_remove(i)
+            .map(|v| (self.trait_accessor.up_box)(v))
+    }
+
+    #[inline(always)]
+    fn check_index(&self, i: usize) -> Result<(), TraitTypeMapError> {
+        let len = self.data.len();
+        if i < len {
+            Ok(())
+        } else {
+            Err(TraitTypeMapError::IndexOutOfBounds { index: i, len })
+        }
     }
 }
 
@@ -105,6 +356,23 @@ pub trait TraitVecStorage<Dyn: ?Sized>: Any {
     fn get_mut(&mut self, idx: usize) -> &mut Dyn;
     fn take_boxed(&mut self, idx: usize) -> Box<Dyn>;
     fn swap_remove(&mut self, idx: usize);
+    /// See [`VecStorage::swap_remove_tracked`].
+    fn swap_remove_tracked(&mut self, idx: usize) -> (Box<Dyn>, Option<usize>);
+    fn try_get(&self, idx: usize) -> Result<&Dyn, TraitTypeMapError>;
+    fn try_get_mut(&mut self, idx: usize) -> Result<&mut Dyn, TraitTypeMapError>;
+    fn try_take_boxed(&mut self, idx: usize) -> Result<Box<Dyn>, TraitTypeMapError>;
+    fn try_swap_remove(&mut self, idx: usize) -> Result<(), TraitTypeMapError>;
+    fn iter_dyn(&self) -> Box<dyn Iterator<Item = (usize, &Dyn)> + '_>;
+    fn iter_dyn_mut(&mut self) -> Box<dyn Iterator<Item = (usize, &mut Dyn)> + '_>;
+    /// Iterate over the indices of all live slots.
+    fn indices(&self) -> Box<dyn Iterator<Item = usize> + '_>;
+    fn storage_type_name(&self) -> &'static str;
+    /// Push a value given as `Box<dyn Any>`, handing it back if it is not of the stored type.
+    fn push_any(&mut self, value: Box<dyn Any>) -> Result<usize, Box<dyn Any>>;
+    /// Register a callback for every value inserted from now on, as a trait object.
+    fn add_insert_hook_dyn(&mut self, hook: Hook<Dyn>);
+    /// Register a callback for every value removed from now on, as a trait object.
+    fn add_remove_hook_dyn(&mut self, hook: Hook<Dyn>);
     fn as_storage_any(&self) -> &dyn Any;
     fn as_storage_any_mut(&mut self) -> &mut dyn Any;
 }
@@ -129,6 +397,55 @@ impl<T: 'static, Dyn: ?Sized + 'static> TraitVecStorage<Dyn> for VecStorage<T, D
         VecStorage::<T, Dyn>::swap_remove(self, idx);
     }
 
+    fn swap_remove_tracked(&mut self, idx: usize) -> (Box<Dyn>, Option<usize>) {
+        let (removed, moved_from) = VecStorage::<T, Dyn>::swap_remove_tracked(self, idx);
+        ((self.trait_accessor.up_box)(removed), moved_from)
+    }
+
+    fn try_get(&self, idx: usize) -> Result<&Dyn, TraitTypeMapError> {
+        VecStorage::<T, Dyn>::try_get_dyn(self, idx)
+    }
+
+    fn try_get_mut(&mut self, idx: usize) -> Result<&mut Dyn, TraitTypeMapError> {
+        VecStorage::<T, Dyn>::try_get_dyn_mut(self, idx)
+    }
+
+    fn try_take_boxed(&mut self, idx: usize) -> Result<Box<Dyn>, TraitTypeMapError> {
+        VecStorage::<T, Dyn>::try_take_boxed(self, idx)
+    }
+
+    fn try_swap_remove(&mut self, idx: usize) -> Result<(), TraitTypeMapError> {
+        VecStorage::<T, Dyn>::try_swap_remove(self, idx).map(drop)
+    }
+
+    fn iter_dyn(&self) -> Box<dyn Iterator<Item = (usize, &Dyn)> + '_> {
+        Box::new(VecStorage::<T, Dyn>::iter_dyn(self))
+    }
+
+    fn iter_dyn_mut(&mut self) -> Box<dyn Iterator<Item = (usize, &mut Dyn)> + '_> {
+        Box::new(VecStorage::<T, Dyn>::iter_dyn_mut(self))
+    }
+
+    fn indices(&self) -> Box<dyn Iterator<Item = usize> + '_> {
+        Box::new(VecStorage::<T, Dyn>::indices(self))
+    }
+
+    fn storage_type_name(&self) -> &'static str {
+        type_name::<Self>()
+    }
+
+    fn push_any(&mut self, value: Box<dyn Any>) -> Result<usize, Box<dyn Any>> {
+        value.downcast::<T>().map(|v| self.push(*v))
+    }
+
+    fn add_insert_hook_dyn(&mut self, hook: Hook<Dyn>) {
+        self.hooks.insert_dyn.push(hook);
+    }
+
+    fn add_remove_hook_dyn(&mut self, hook: Hook<Dyn>) {
+        self.hooks.remove_dyn.push(hook);
+    }
+
     fn as_storage_any(&self) -> &dyn Any {
         self
     }
@@ -141,422 +458,4508 @@ impl<T: 'static, Dyn: ?Sized + 'static> TraitVecStorage<Dyn> for VecStorage<T, D
 /// Marker type for the vector storage family.
 pub struct VecFamily;
 
-/* ==================== Vector Option backend ==================== */
+/* ==================== Change tracking ==================== */
 
-/// Storage for multiple optional values of a single type in a vector.
-///
-/// Values can be accessed by index, and removed values leave `None` in their place.
-pub struct VecOptionStorage<T, Dyn: ?Sized> {
-    pub data: Vec<Option<T>>,
-    trait_accessor: TraitAccessor<T, Dyn>,
-    /// Cached count of non-None elements for O(1) len()
-    count: usize,
-}
-impl<T, Dyn: ?Sized> VecOptionStorage<T, Dyn> {
-    pub fn new(trait_accessor: TraitAccessor<T, Dyn>) -> Self {
-        Self {
-            data: Vec::new(),
-            trait_accessor,
-            count: 0,
-        }
-    }
+/// Set of changes recorded for a storage slot since the last [`drain_changes`](VecOptionStorage::drain_changes).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct ChangeFlags(u8);
+
+impl ChangeFlags {
+    pub const NONE: Self = Self(0);
+    /// A value was stored into the slot.
+    pub const ADDED: Self = Self(1);
+    /// The value in the slot was borrowed mutably.
+    pub const MODIFIED: Self = Self(1 << 1);
+    /// The value was removed from the slot.
+    pub const REMOVED: Self = Self(1 << 2);
 
     #[inline(always)]
-    pub fn push(&mut self, v: T) -> usize {
-        let idx = self.data.len();
-        self.data.push(Some(v));
-        self.count += 1;
-        idx
+    pub const fn bits(self) -> u8 {
+        self.0
     }
 
-    #[inline]
-    pub fn iter(&self) -> impl Iterator<Item = &T> {
-        self.data.iter().filter_map(|o| o.as_ref())
+    #[inline(always)]
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
     }
 
+    /// Whether every flag set in `other` is also set in `self`.
     #[inline(always)]
-    pub fn get(&self, i: usize) -> Option<&T> {
-        self.data.get(i).and_then(|o| o.as_ref())
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
     }
+}
 
-    #[inline(always)]
-    pub fn get_mut(&mut self, i: usize) -> Option<&mut T> {
-        self.data.get_mut(i).and_then(|o| o.as_mut())
+impl std::ops::BitOr for ChangeFlags {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
     }
+}
 
-    #[inline(always)]
-    pub fn take(&mut self, i: usize) -> Option<T> {
-        let result = self.data.get_mut(i).and_then(|o| o.take());
-        if result.is_some() {
-            self.count -= 1;
+impl std::ops::BitOrAssign for ChangeFlags {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+/// Per-slot change flags and the tick of each slot's latest change.
+#[derive(Clone)]
+struct ChangeTracker {
+    enabled: bool,
+    tick: u64,
+    flags: Vec<ChangeFlags>,
+    /// Tick of the latest change per slot, `0` if it never changed.
+    ticks: Vec<u64>,
+}
+
+impl Default for ChangeTracker {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            tick: 1,
+            flags: Vec::new(),
+            ticks: Vec::new(),
         }
-        result
     }
+}
 
+impl ChangeTracker {
     #[inline(always)]
-    pub fn get_dyn(&self, i: usize) -> Option<&Dyn> {
-        self.get(i).map(|v| (self.trait_accessor.up_ref)(v))
+    fn record(&mut self, i: usize, change: ChangeFlags) {
+        if !self.enabled {
+            return;
+        }
+        if self.flags.len() <= i {
+            self.flags.resize(i + 1, ChangeFlags::NONE);
+            self.ticks.resize(i + 1, 0);
+        }
+        self.flags[i] |= change;
+        self.ticks[i] = self.tick;
     }
 
-    #[inline(always)]
-    pub fn get_dyn_mut(&mut self, i: usize) -> Option<&mut Dyn> {
-        let up_mut = self.trait_accessor.up_mut;
-        self.get_mut(i).map(up_mut)
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        if !enabled {
+            self.flags.clear();
+            self.ticks.clear();
+        }
     }
 
-    #[inline(always)]
-    pub fn take_boxed(&mut self, i: usize) -> Option<Box<Dyn>> {
-        self.take(i).map(|v| (self.trait_accessor.up_box)(v))
+    fn drain(&mut self) -> Vec<(usize, ChangeFlags)> {
+        self.flags
+            .iter_mut()
+            .enumerate()
+            .filter(|(_, f)| !f.is_empty())
+            .map(|(i, f)| (i, std::mem::take(f)))
+            .collect()
     }
 
-    pub fn swap_remove(&mut self, i: usize) -> Option<T> {
-        self.data.swap_remove(i)
+    fn changed_since(&self, tick: u64) -> Vec<usize> {
+        self.ticks
+            .iter()
+            .enumerate()
+            .filter(|&(_, &t)| t != 0 && t >= tick)
+            .map(|(i, _)| i)
+            .collect()
     }
 }
 
-/// Trait object interface for vector option storage.
+/* ==================== Vector Option backend ==================== */
+
+/// Generational handle to a value stored in a [`VecOptionStorage`] or [`SparseSetStorage`].
 ///
-/// This allows accessing stored values as trait objects without knowing the concrete type.
-pub trait TraitVecOptionStorage<Dyn: ?Sized>: Any {
-    fn len(&self) -> usize;
-    fn is_empty(&self) -> bool {
-        self.len() == 0
-    }
-    fn get(&self, idx: usize) -> Option<&Dyn>;
-    fn get_mut(&mut self, idx: usize) -> Option<&mut Dyn>;
-    fn take_boxed(&mut self, idx: usize) -> Option<Box<Dyn>>;
-    fn swap_remove(&mut self, idx: usize);
-    fn as_storage_any(&self) -> &dyn Any;
-    fn as_storage_any_mut(&mut self) -> &mut dyn Any;
+/// A handle remembers the generation of its slot (or key) at insertion time, so once the value
+/// is removed (or moved by `swap_remove`) the handle goes stale and lookups return `None`
+/// instead of aliasing whatever value occupies the slot next.
+pub struct Handle<T> {
+    index: usize,
+    generation: u32,
+    _marker: PhantomData<fn() -> T>,
 }
-impl<T: 'static, Dyn: ?Sized + 'static> TraitVecOptionStorage<Dyn> for VecOptionStorage<T, Dyn> {
-    fn len(&self) -> usize {
-        self.count
-    }
 
-    #[inline]
-    fn get(&self, idx: usize) -> Option<&Dyn> {
-        VecOptionStorage::<T, Dyn>::get_dyn(self, idx)
+impl<T> Handle<T> {
+    /// Index of the slot this handle points to.
+    #[inline(always)]
+    pub fn index(&self) -> usize {
+        self.index
     }
 
-    #[inline]
-    fn get_mut(&mut self, idx: usize) -> Option<&mut Dyn> {
-        VecOptionStorage::<T, Dyn>::get_dyn_mut(self, idx)
+    /// Generation of the slot at the time the handle was created.
+    #[inline(always)]
+    pub fn generation(&self) -> u32 {
+        self.generation
     }
+}
 
-    #[inline]
-    fn take_boxed(&mut self, idx: usize) -> Option<Box<Dyn>> {
-        VecOptionStorage::<T, Dyn>::take_boxed(self, idx)
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        *self
     }
+}
 
-    fn swap_remove(&mut self, idx: usize) {
-        self.data.swap_remove(idx);
-    }
+impl<T> Copy for Handle<T> {}
 
-    fn as_storage_any(&self) -> &dyn Any {
-        self
+impl<T> PartialEq for Handle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index && self.generation == other.generation
     }
+}
 
-    fn as_storage_any_mut(&mut self) -> &mut dyn Any {
-        self
+impl<T> Eq for Handle<T> {}
+
+impl<T> Hash for Handle<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.index.hash(state);
+        self.generation.hash(state);
     }
 }
 
-/// Marker type for the vector option storage family.
-pub struct VecOptionFamily;
-
-/* ==================== Single Option backend ==================== */
+impl<T> fmt::Debug for Handle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Handle")
+            .field("index", &self.index)
+            .field("generation", &self.generation)
+            .finish()
+    }
+}
 
-/// Storage for a single optional value of a type.
-pub struct OptionStorage<T, Dyn: ?Sized> {
-    pub data: Option<T>,
+/// Storage for multiple optional values of a single type in a vector.
+///
+/// Values can be accessed by index, and removed values leave `None` in their place.
+/// Values can also be accessed through generational [`Handle`]s, which detect stale indices.
+///
+/// Vacated slots are kept in a free list and reused by `push`, so storages with churn don't
+/// grow unbounded. Use [`set_reuse_slots(false)`](Self::set_reuse_slots) if you rely on
+/// indices being handed out in increasing order, and [`compact`](Self::compact) to drop holes.
+pub struct VecOptionStorage<T, Dyn: ?Sized> {
+    pub data: Vec<Option<T>>,
     trait_accessor: TraitAccessor<T, Dyn>,
+    /// Cached count of non-None elements for O(1) len()
+    count: usize,
+    /// Generation of each slot, bumped whenever the value in the slot is removed or replaced.
+    /// May be longer than `data` so that slots dropped by `swap_remove` keep their generation.
+    generations: Vec<u32>,
+    /// Indices of vacated slots available for reuse
+    free: Vec<usize>,
+    reuse_slots: bool,
+    changes: ChangeTracker,
+    hooks: StorageHooks<T, Dyn>,
 }
-impl<T, Dyn: ?Sized> OptionStorage<T, Dyn> {
+impl<T: Clone, Dyn: ?Sized> Clone for VecOptionStorage<T, Dyn> {
+    fn clone(&self) -> Self {
+        Self {
+            data: self.data.clone(),
+            trait_accessor: self.trait_accessor,
+            count: self.count,
+            generations: self.generations.clone(),
+            free: self.free.clone(),
+            reuse_slots: self.reuse_slots,
+            changes: self.changes.clone(),
+            hooks: StorageHooks::default(),
+        }
+    }
+}
+
+impl<T, Dyn: ?Sized + DynClone> VecOptionStorage<T, Dyn> {
+    /// Clone the value at `i` into a new boxed trait object, leaving the stored value in place.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use trait_type_map::dyn_clone::{clone_trait_object, DynClone};
+    /// use trait_type_map::{impl_trait_accessible, TraitTypeMap, VecOptionFamily};
+    ///
+    /// trait Shape: DynClone {
+    ///     fn area(&self) -> f32;
+    /// }
+    /// clone_trait_object!(Shape);
+    ///
+    /// #[derive(Clone)]
+    /// struct Square(f32);
+    /// impl Shape for Square {
+    ///     fn area(&self) -> f32 { self.0 * self.0 }
+    /// }
+    /// impl_trait_accessible!(dyn Shape; Square);
+    ///
+    /// let mut map: TraitTypeMap<dyn Shape, VecOptionFamily> = TraitTypeMap::new();
+    /// map.register_type_storage::<Square>();
+    /// let idx = map.get_storage_mut::<Square>().push(Square(2.0));
+    ///
+    /// let shape: Box<dyn Shape> = map.get_storage::<Square>().clone_boxed(idx).unwrap();
+    /// let copy = shape.clone();
+    /// assert_eq!(copy.area(), 4.0);
+    /// ```
+    pub fn clone_boxed(&self, i: usize) -> Option<Box<Dyn>> {
+        self.get_dyn(i).map(dyn_clone::clone_box)
+    }
+}
+
+impl<T, Dyn: ?Sized> VecOptionStorage<T, Dyn> {
     pub fn new(trait_accessor: TraitAccessor<T, Dyn>) -> Self {
         Self {
-            data: None,
+            data: Vec::new(),
             trait_accessor,
+            count: 0,
+            generations: Vec::new(),
+            free: Vec::new(),
+            reuse_slots: true,
+            changes: ChangeTracker::default(),
+            hooks: StorageHooks::default(),
         }
     }
 
-    #[inline(always)]
-    pub fn set(&mut self, v: T) {
-        self.data = Some(v);
+    /// Create a storage from existing slots, keeping every value at its index.
+    pub fn from_slots(trait_accessor: TraitAccessor<T, Dyn>, data: Vec<Option<T>>) -> Self {
+        Self::from_slots_and_generations(trait_accessor, data, Vec::new())
     }
 
-    #[inline(always)]
-    pub fn get(&self) -> Option<&T> {
-        self.data.as_ref()
+    /// Create a storage from existing slots and their generations, so handles to them stay valid.
+    ///
+    /// Slots without a generation start at `0`.
+    pub(crate) fn from_slots_and_generations(
+        trait_accessor: TraitAccessor<T, Dyn>,
+        data: Vec<Option<T>>,
+        mut generations: Vec<u32>,
+    ) -> Self {
+        if generations.len() < data.len() {
+            generations.resize(data.len(), 0);
+        }
+        let count = data.iter().filter(|o| o.is_some()).count();
+        // Reversed so that `push` reuses the lowest free index first
+        let free = (0..data.len())
+            .rev()
+            .filter(|&i| data[i].is_none())
+            .collect();
+        Self {
+            generations,
+            data,
+            trait_accessor,
+            count,
+            free,
+            reuse_slots: true,
+            changes: ChangeTracker::default(),
+            hooks: StorageHooks::default(),
+        }
     }
 
-    #[inline(always)]
-    pub fn get_mut(&mut self) -> Option<&mut T> {
-        self.data.as_mut()
+    /// Enable or disable reuse of vacated slots by `push` (enabled by default).
+    ///
+    /// With reuse disabled, `push` always appends and indices are handed out in increasing order.
+    /// Re-enabling reuse makes every hole left in the meantime available again.
+    pub fn set_reuse_slots(&mut self, reuse: bool) {
+        if reuse && !self.reuse_slots {
+            self.rebuild_free();
+        } else if !reuse {
+            self.free.clear();
+        }
+        self.reuse_slots = reuse;
     }
 
-    #[inline(always)]
-    pub fn take(&mut self) -> Option<T> {
-        self.data.take()
+    /// Whether `push` reuses vacated slots.
+    pub fn reuses_slots(&self) -> bool {
+        self.reuse_slots
     }
 
-    #[inline(always)]
-    pub fn is_some(&self) -> bool {
-        self.data.is_some()
+    /// Replace the free list with exactly the current holes, dropping stale and duplicate entries.
+    fn rebuild_free(&mut self) {
+        // Reversed so that `push` reuses the lowest free index first
+        self.free = (0..self.data.len())
+            .rev()
+            .filter(|&i| self.data[i].is_none())
+            .collect();
     }
 
-    #[inline(always)]
-    pub fn get_dyn(&self) -> Option<&Dyn> {
-        self.get().map(|v| (self.trait_accessor.up_ref)(v))
+    /// Call `hook` with the index and value of every value pushed from now on.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::sync::{Arc, Mutex};
+    /// use trait_type_map::{impl_trait_accessible, TraitTypeMap, VecOptionFamily};
+    ///
+    /// trait Named {}
+    /// struct Item(u32);
+    /// impl Named for Item {}
+    /// impl_trait_accessible!(dyn Named; Item);
+    ///
+    /// let mut map: TraitTypeMap<dyn Named, VecOptionFamily> = TraitTypeMap::new();
+    /// map.register_type_storage::<Item>();
+    /// let storage = map.get_storage_mut::<Item>();
+    ///
+    /// let live = Arc::new(Mutex::new(Vec::new()));
+    /// let on_insert = live.clone();
+    /// let on_remove = live.clone();
+    /// storage.on_insert(move |i, item: &Item| on_insert.lock().unwrap().push((i, item.0)));
+    /// storage.on_remove(move |i, _: &Item| on_remove.lock().unwrap().retain(|&(j, _)| j != i));
+    ///
+    /// storage.push(Item(10));
+    /// storage.push(Item(20));
+    /// storage.take(0);
+    /// assert_eq!(*live.lock().unwrap(), [(1, 20)]);
+    /// ```
+    pub fn on_insert(&mut self, hook: impl Fn(usize, &T) + Send + Sync + 'static) {
+        self.hooks.insert.push(Arc::new(hook));
     }
 
-    #[inline(always)]
-    pub fn get_dyn_mut(&mut self) -> Option<&mut Dyn> {
-        let up_mut = self.trait_accessor.up_mut;
+    /// Call `hook` with the index and value of every value removed from now on, before it is returned.
+    pub fn on_remove(&mut self, hook: impl Fn(usize, &T) + Send + Sync + 'static) {
+        self.hooks.remove.push(Arc::new(hook));
+    }
+
+    /// Enable or disable change tracking (disabled by default). Disabling clears recorded changes.
+    ///
+    /// While enabled, `push` marks slots [`ADDED`](ChangeFlags::ADDED), `get_mut` and `get_dyn_mut`
+    /// mark them [`MODIFIED`](ChangeFlags::MODIFIED) and `take` marks them [`REMOVED`](ChangeFlags::REMOVED).
+    /// Values moved by `swap_remove` or `compact` mark their old slot removed and their new slot modified.
+    /// Writes through the public `data` field are not tracked.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use trait_type_map::{impl_trait_accessible, ChangeFlags, TraitTypeMap, VecOptionFamily};
+    ///
+    /// trait Named {}
+    /// struct Item(u32);
+    /// impl Named for Item {}
+    /// impl_trait_accessible!(dyn Named; Item);
+    ///
+    /// let mut map: TraitTypeMap<dyn Named, VecOptionFamily> = TraitTypeMap::new();
+    /// map.register_type_storage::<Item>();
+    /// let storage = map.get_storage_mut::<Item>();
+    /// storage.set_change_tracking(true);
+    ///
+    /// storage.push(Item(0));
+    /// storage.push(Item(1));
+    /// storage.get_mut(0).unwrap().0 = 5;
+    /// storage.take(1);
+    ///
+    /// assert_eq!(
+    ///     storage.drain_changes(),
+    ///     [
+    ///         (0, ChangeFlags::ADDED | ChangeFlags::MODIFIED),
+    ///         (1, ChangeFlags::ADDED | ChangeFlags::REMOVED),
+    ///     ]
+    /// );
+    /// assert!(storage.drain_changes().is_empty());
+    /// ```
+    pub fn set_change_tracking(&mut self, enabled: bool) {
+        self.changes.set_enabled(enabled);
+    }
+
+    /// Whether change tracking is enabled.
+    pub fn tracks_changes(&self) -> bool {
+        self.changes.enabled
+    }
+
+    /// Set the tick recorded for subsequent changes. Kept in sync by [`TraitTypeMap::increment_change_tick`].
+    pub fn set_change_tick(&mut self, tick: u64) {
+        self.changes.tick = tick;
+    }
+
+    /// Return the flags of every changed slot in index order, and clear them.
+    pub fn drain_changes(&mut self) -> Vec<(usize, ChangeFlags)> {
+        self.changes.drain()
+    }
+
+    /// Indices of slots whose latest change happened at or after `tick`.
+    pub fn changed_since(&self, tick: u64) -> Vec<usize> {
+        self.changes.changed_since(tick)
+    }
+
+    #[inline(always)]
+    pub fn push(&mut self, v: T) -> usize {
+        if self.reuse_slots {
+            while let Some(idx) = self.free.pop() {
+                // `data` is public, so skip entries that no longer point at a hole
+                if let Some(slot @ None) = self.data.get_mut(idx) {
+                    let value = slot.insert(v);
+                    self.hooks.inserted(idx, value, self.trait_accessor.up_ref);
+                    self.count += 1;
+                    self.changes.record(idx, ChangeFlags::ADDED);
+                    return idx;
+                }
+            }
+        }
+        let idx = self.data.len();
+        self.data.push(Some(v));
+        if self.generations.len() <= idx {
+            // `data` is public, so it may have grown without `generations`
+            self.generations.resize(idx + 1, 0);
+        }
+        self.count += 1;
+        self.changes.record(idx, ChangeFlags::ADDED);
+        if let Some(Some(value)) = self.data.last() {
+            self.hooks.inserted(idx, value, self.trait_accessor.up_ref);
+        }
+        idx
+    }
+
+    /// Push a value and return a generational handle to it.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use trait_type_map::{impl_trait_accessible, TraitTypeMap, VecOptionFamily};
+    ///
+    /// trait Named {}
+    /// struct Item(u32);
+    /// impl Named for Item {}
+    /// impl_trait_accessible!(dyn Named; Item);
+    ///
+    /// let mut map: TraitTypeMap<dyn Named, VecOptionFamily> = TraitTypeMap::new();
+    /// map.register_type_storage::<Item>();
+    /// let storage = map.get_storage_mut::<Item>();
+    ///
+    /// let a = storage.insert(Item(1));
+    /// let b = storage.insert(Item(2));
+    /// assert_eq!(storage.remove(a).map(|item| item.0), Some(1));
+    ///
+    /// // `a` is stale now, `b` is unaffected
+    /// assert!(storage.get_by_handle(a).is_none());
+    /// assert!(storage.remove(a).is_none());
+    /// assert_eq!(storage.get_by_handle(b).map(|item| item.0), Some(2));
+    /// ```
+    #[inline]
+    pub fn insert(&mut self, v: T) -> Handle<T> {
+        let index = self.push(v);
+        Handle {
+            index,
+            generation: self.generation(index),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Handle to the value currently stored at index `i`, if any.
+    #[inline]
+    pub fn handle(&self, i: usize) -> Option<Handle<T>> {
+        self.get(i)?;
+        Some(Handle {
+            index: i,
+            generation: self.generation(i),
+            _marker: PhantomData,
+        })
+    }
+
+    /// Whether `handle` still refers to a live value.
+    #[inline]
+    pub fn contains(&self, handle: Handle<T>) -> bool {
+        self.get_by_handle(handle).is_some()
+    }
+
+    /// Get the value `handle` refers to, or `None` if the handle is stale.
+    #[inline]
+    pub fn get_by_handle(&self, handle: Handle<T>) -> Option<&T> {
+        if self.is_current(handle) {
+            self.get(handle.index)
+        } else {
+            None
+        }
+    }
+
+    /// Mutable counterpart of [`get_by_handle`](Self::get_by_handle).
+    #[inline]
+    pub fn get_mut_by_handle(&mut self, handle: Handle<T>) -> Option<&mut T> {
+        if self.is_current(handle) {
+            self.get_mut(handle.index)
+        } else {
+            None
+        }
+    }
+
+    /// Remove and return the value `handle` refers to, or `None` if the handle is stale.
+    #[inline]
+    pub fn remove(&mut self, handle: Handle<T>) -> Option<T> {
+        if self.is_current(handle) {
+            self.take(handle.index)
+        } else {
+            None
+        }
+    }
+
+    /// Generation of every slot, possibly longer than `data`.
+    #[cfg_attr(not(feature = "serde"), allow(dead_code))]
+    pub(crate) fn generations(&self) -> &[u32] {
+        &self.generations
+    }
+
+    /// Generation of slot `i`; slots added directly through `data` start at `0`.
+    #[inline(always)]
+    fn generation(&self, i: usize) -> u32 {
+        self.generations.get(i).copied().unwrap_or(0)
+    }
+
+    #[inline(always)]
+    fn is_current(&self, handle: Handle<T>) -> bool {
+        self.generation(handle.index) == handle.generation
+    }
+
+    #[inline(always)]
+    fn bump_generation(&mut self, i: usize) {
+        if self.generations.len() <= i {
+            self.generations.resize(i + 1, 0);
+        }
+        self.generations[i] = self.generations[i].wrapping_add(1);
+    }
+
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.data.iter().filter_map(|o| o.as_ref())
+    }
+
+    /// Iterate over all stored values along with their slot indices, skipping holes.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use trait_type_map::{impl_trait_accessible, TraitTypeMap, VecOptionFamily};
+    ///
+    /// trait Named {
+    ///     fn name(&self) -> &str;
+    /// }
+    /// struct Item(&'static str);
+    /// impl Named for Item {
+    ///     fn name(&self) -> &str { self.0 }
+    /// }
+    /// impl_trait_accessible!(dyn Named; Item);
+    ///
+    /// let mut map: TraitTypeMap<dyn Named, VecOptionFamily> = TraitTypeMap::new();
+    /// map.register_type_storage::<Item>();
+    /// let storage = map.get_storage_mut::<Item>();
+    /// storage.push(Item("a"));
+    /// storage.push(Item("b"));
+    /// storage.push(Item("c"));
+    /// storage.take(1);
+    ///
+    /// let names: Vec<_> = storage.iter_indexed().map(|(i, item)| (i, item.0)).collect();
+    /// assert_eq!(names, [(0, "a"), (2, "c")]);
+    /// assert_eq!(storage.indices().collect::<Vec<_>>(), [0, 2]);
+    ///
+    /// let storage = map.get_trait_storage(std::any::TypeId::of::<Item>()).unwrap();
+    /// let names: Vec<_> = storage.iter_dyn().map(|(i, v)| (i, v.name())).collect();
+    /// assert_eq!(names, [(0, "a"), (2, "c")]);
+    /// ```
+    #[inline]
+    pub fn iter_indexed(&self) -> impl Iterator<Item = (usize, &T)> {
+        self.data
+            .iter()
+            .enumerate()
+            .filter_map(|(i, o)| o.as_ref().map(|v| (i, v)))
+    }
+
+    /// Mark every live slot [`MODIFIED`](ChangeFlags::MODIFIED) before handing out mutable access to all of them.
+    pub(crate) fn mark_live_modified(&mut self) {
+        for (i, o) in self.data.iter().enumerate() {
+            if o.is_some() {
+                self.changes.record(i, ChangeFlags::MODIFIED);
+            }
+        }
+    }
+
+    /// Mutable counterpart of [`iter_indexed`](Self::iter_indexed).
+    ///
+    /// With change tracking enabled, every live slot is marked [`MODIFIED`](ChangeFlags::MODIFIED).
+    pub fn iter_indexed_mut(&mut self) -> impl Iterator<Item = (usize, &mut T)> {
+        self.mark_live_modified();
+        self.data
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(i, o)| o.as_mut().map(|v| (i, v)))
+    }
+
+    /// Iterate over all stored values as trait objects, along with their slot indices.
+    pub fn iter_dyn(&self) -> impl Iterator<Item = (usize, &Dyn)> {
+        let up_ref = self.trait_accessor.up_ref;
+        self.iter_indexed().map(move |(i, v)| (i, up_ref(v)))
+    }
+
+    pub fn iter_dyn_mut(&mut self) -> impl Iterator<Item = (usize, &mut Dyn)> {
+        let up_mut = self.trait_accessor.up_mut;
+        self.iter_indexed_mut().map(move |(i, v)| (i, up_mut(v)))
+    }
+
+    /// Indices of all live slots.
+    pub fn indices(&self) -> impl Iterator<Item = usize> + '_ {
+        self.iter_indexed().map(|(i, _)| i)
+    }
+
+    #[inline(always)]
+    pub fn get(&self, i: usize) -> Option<&T> {
+        self.data.get(i).and_then(|o| o.as_ref())
+    }
+
+    #[inline(always)]
+    pub fn get_mut(&mut self, i: usize) -> Option<&mut T> {
+        let value = self.data.get_mut(i).and_then(|o| o.as_mut());
+        if value.is_some() {
+            self.changes.record(i, ChangeFlags::MODIFIED);
+        }
+        value
+    }
+
+    #[inline(always)]
+    pub fn take(&mut self, i: usize) -> Option<T> {
+        let result = self.data.get_mut(i).and_then(|o| o.take());
+        if let Some(value) = &result {
+            self.hooks.removed(i, value, self.trait_accessor.up_ref);
+            self.count -= 1;
+            self.bump_generation(i);
+            if self.reuse_slots {
+                self.free.push(i);
+            }
+            self.changes.record(i, ChangeFlags::REMOVED);
+        }
+        result
+    }
+
+    #[inline(always)]
+    pub fn get_dyn(&self, i: usize) -> Option<&Dyn> {
+        self.get(i).map(|v| (self.trait_accessor.up_ref)(v))
+    }
+
+    #[inline(always)]
+    pub fn get_dyn_mut(&mut self, i: usize) -> Option<&mut Dyn> {
+        let up_mut = self.trait_accessor.up_mut;
+        self.get_mut(i).map(up_mut)
+    }
+
+    #[inline(always)]
+    pub fn take_boxed(&mut self, i: usize) -> Option<Box<Dyn>> {
+        self.take(i).map(|v| (self.trait_accessor.up_box)(v))
+    }
+
+    /// Remove the slot at `i`, moving the last slot into its place.
+    ///
+    /// This invalidates the index of the last value; use
+    /// [`swap_remove_tracked`](Self::swap_remove_tracked) to find out which index moved.
+    pub fn swap_remove(&mut self, i: usize) -> Option<T> {
+        self.swap_remove_tracked(i).0
+    }
+
+    /// Remove the slot at `i`, moving the last slot into its place.
+    ///
+    /// Returns the removed value (`None` if the slot was a hole) and, if a value was moved,
+    /// the index it was moved from (it now lives at `i`). Amortized O(1), as free slots that
+    /// became stale are skipped by `push` rather than searched for here.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::any::TypeId;
+    /// use trait_type_map::{impl_trait_accessible, TraitTypeMap, VecOptionFamily};
+    ///
+    /// trait Named {}
+    /// struct Item(u32);
+    /// impl Named for Item {}
+    /// impl_trait_accessible!(dyn Named; Item);
+    ///
+    /// let mut map: TraitTypeMap<dyn Named, VecOptionFamily> = TraitTypeMap::new();
+    /// map.register_type_storage::<Item>();
+    /// let storage = map.get_storage_mut::<Item>();
+    /// storage.push(Item(0));
+    /// storage.push(Item(1));
+    /// storage.push(Item(2));
+    ///
+    /// let (removed, moved_from) = storage.swap_remove_tracked(0);
+    /// assert_eq!(removed.map(|item| item.0), Some(0));
+    /// assert_eq!(moved_from, Some(2));
+    /// assert_eq!(storage.get(0).map(|item| item.0), Some(2));
+    ///
+    /// let trait_storage = map.get_trait_storage(TypeId::of::<Item>()).unwrap();
+    /// assert_eq!(trait_storage.len(), 2);
+    /// ```
+    pub fn swap_remove_tracked(&mut self, i: usize) -> (Option<T>, Option<usize>) {
+        let last = self.data.len().wrapping_sub(1);
+        let removed = self.data.swap_remove(i);
+        if removed.is_some() {
+            self.count -= 1;
+        }
+        // Both the removed slot and the vacated last slot now refer to different values
+        self.bump_generation(i);
+        if last != i {
+            self.bump_generation(last);
+        }
+        // Entries for `i` and `last` may be stale now; `push` skips those instead of this
+        // scanning the free list, which is only rebuilt once it outgrows the storage (amortized O(1))
+        if self.free.len() > 2 * self.data.len() {
+            self.rebuild_free();
+        }
+        if let Some(value) = &removed {
+            self.hooks.removed(i, value, self.trait_accessor.up_ref);
+            self.changes.record(i, ChangeFlags::REMOVED);
+        }
+        let moved_from = match self.data.get(i) {
+            Some(Some(_)) => {
+                self.changes.record(last, ChangeFlags::REMOVED);
+                self.changes.record(i, ChangeFlags::MODIFIED);
+                Some(last)
+            }
+            Some(None) => {
+                if self.reuse_slots {
+                    self.free.push(i);
+                }
+                None
+            }
+            None => None,
+        };
+        (removed, moved_from)
+    }
+
+    /// Move all values to the front of the storage, removing every hole.
+    ///
+    /// Returns a remapping table indexed by old index: `Some(new_index)` for values that were
+    /// kept, `None` for holes. Handles to moved values become stale.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use trait_type_map::{impl_trait_accessible, TraitTypeMap, VecOptionFamily};
+    ///
+    /// trait Named {}
+    /// struct Item(u32);
+    /// impl Named for Item {}
+    /// impl_trait_accessible!(dyn Named; Item);
+    ///
+    /// let mut map: TraitTypeMap<dyn Named, VecOptionFamily> = TraitTypeMap::new();
+    /// map.register_type_storage::<Item>();
+    /// let storage = map.get_storage_mut::<Item>();
+    ///
+    /// for i in 0..4 {
+    ///     storage.push(Item(i));
+    /// }
+    /// storage.take(0);
+    /// storage.take(2);
+    ///
+    /// assert_eq!(storage.compact(), vec![None, Some(0), None, Some(1)]);
+    /// assert_eq!(storage.get(1).map(|item| item.0), Some(3));
+    /// ```
+    pub fn compact(&mut self) -> Vec<Option<usize>> {
+        let mut remap = Vec::with_capacity(self.data.len());
+        let mut next = 0;
+        for i in 0..self.data.len() {
+            if self.data[i].is_some() {
+                self.data.swap(next, i);
+                remap.push(Some(next));
+                next += 1;
+            } else {
+                remap.push(None);
+            }
+        }
+        self.data.truncate(next);
+        for (old, new) in remap.iter().enumerate() {
+            if *new != Some(old) {
+                self.bump_generation(old);
+            }
+            if let Some(new) = *new {
+                if new != old {
+                    self.changes.record(old, ChangeFlags::REMOVED);
+                    self.changes.record(new, ChangeFlags::MODIFIED);
+                }
+            }
+        }
+        self.free.clear();
+        remap
+    }
+
+    /// Checked counterpart of [`swap_remove`](Self::swap_remove).
+    pub fn try_swap_remove(&mut self, i: usize) -> Result<Option<T>, TraitTypeMapError> {
+        let len = self.data.len();
+        if i < len {
+            Ok(self.swap_remove(i))
+        } else {
+            Err(TraitTypeMapError::IndexOutOfBounds { index: i, len })
+        }
+    }
+}
+
+/// Trait object interface for vector option storage.
+///
+/// This allows accessing stored values as trait objects without knowing the concrete type.
+pub trait TraitVecOptionStorage<Dyn: ?Sized>: Any {
+    fn len(&self) -> usize;
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    fn get(&self, idx: usize) -> Option<&Dyn>;
+    fn get_mut(&mut self, idx: usize) -> Option<&mut Dyn>;
+    fn take_boxed(&mut self, idx: usize) -> Option<Box<Dyn>>;
+    fn swap_remove(&mut self, idx: usize);
+    /// See [`VecOptionStorage::swap_remove_tracked`].
+    fn swap_remove_tracked(&mut self, idx: usize) -> (Option<Box<Dyn>>, Option<usize>);
+    fn try_swap_remove(&mut self, idx: usize) -> Result<(), TraitTypeMapError>;
+    fn iter_dyn(&self) -> Box<dyn Iterator<Item = (usize, &Dyn)> + '_>;
+    fn iter_dyn_mut(&mut self) -> Box<dyn Iterator<Item = (usize, &mut Dyn)> + '_>;
+    /// Iterate over the indices of all live slots.
+    fn indices(&self) -> Box<dyn Iterator<Item = usize> + '_>;
+    fn storage_type_name(&self) -> &'static str;
+    /// Push a value given as `Box<dyn Any>`, handing it back if it is not of the stored type.
+    fn push_any(&mut self, value: Box<dyn Any>) -> Result<usize, Box<dyn Any>>;
+    fn set_change_tick(&mut self, tick: u64);
+    fn drain_changes(&mut self) -> Vec<(usize, ChangeFlags)>;
+    fn changed_since(&self, tick: u64) -> Vec<usize>;
+    /// Register a callback for every value inserted from now on, as a trait object.
+    fn add_insert_hook_dyn(&mut self, hook: Hook<Dyn>);
+    /// Register a callback for every value removed from now on, as a trait object.
+    fn add_remove_hook_dyn(&mut self, hook: Hook<Dyn>);
+    fn as_storage_any(&self) -> &dyn Any;
+    fn as_storage_any_mut(&mut self) -> &mut dyn Any;
+}
+impl<T: 'static, Dyn: ?Sized + 'static> TraitVecOptionStorage<Dyn> for VecOptionStorage<T, Dyn> {
+    fn len(&self) -> usize {
+        self.count
+    }
+
+    #[inline]
+    fn get(&self, idx: usize) -> Option<&Dyn> {
+        VecOptionStorage::<T, Dyn>::get_dyn(self, idx)
+    }
+
+    #[inline]
+    fn get_mut(&mut self, idx: usize) -> Option<&mut Dyn> {
+        VecOptionStorage::<T, Dyn>::get_dyn_mut(self, idx)
+    }
+
+    #[inline]
+    fn take_boxed(&mut self, idx: usize) -> Option<Box<Dyn>> {
+        VecOptionStorage::<T, Dyn>::take_boxed(self, idx)
+    }
+
+    fn swap_remove(&mut self, idx: usize) {
+        VecOptionStorage::<T, Dyn>::swap_remove(self, idx);
+    }
+
+    fn swap_remove_tracked(&mut self, idx: usize) -> (Option<Box<Dyn>>, Option<usize>) {
+        let (removed, moved_from) = VecOptionStorage::<T, Dyn>::swap_remove_tracked(self, idx);
+        let up_box = self.trait_accessor.up_box;
+        (removed.map(up_box), moved_from)
+    }
+
+    fn try_swap_remove(&mut self, idx: usize) -> Result<(), TraitTypeMapError> {
+        VecOptionStorage::<T, Dyn>::try_swap_remove(self, idx).map(drop)
+    }
+
+    fn iter_dyn(&self) -> Box<dyn Iterator<Item = (usize, &Dyn)> + '_> {
+        Box::new(VecOptionStorage::<T, Dyn>::iter_dyn(self))
+    }
+
+    fn iter_dyn_mut(&mut self) -> Box<dyn Iterator<Item = (usize, &mut Dyn)> + '_> {
+        Box::new(VecOptionStorage::<T, Dyn>::iter_dyn_mut(self))
+    }
+
+    fn indices(&self) -> Box<dyn Iterator<Item = usize> + '_> {
+        Box::new(VecOptionStorage::<T, Dyn>::indices(self))
+    }
+
+    fn storage_type_name(&self) -> &'static str {
+        type_name::<Self>()
+    }
+
+    fn push_any(&mut self, value: Box<dyn Any>) -> Result<usize, Box<dyn Any>> {
+        value.downcast::<T>().map(|v| self.push(*v))
+    }
+
+    fn set_change_tick(&mut self, tick: u64) {
+        VecOptionStorage::<T, Dyn>::set_change_tick(self, tick);
+    }
+
+    fn drain_changes(&mut self) -> Vec<(usize, ChangeFlags)> {
+        VecOptionStorage::<T, Dyn>::drain_changes(self)
+    }
+
+    fn changed_since(&self, tick: u64) -> Vec<usize> {
+        VecOptionStorage::<T, Dyn>::changed_since(self, tick)
+    }
+
+    fn add_insert_hook_dyn(&mut self, hook: Hook<Dyn>) {
+        self.hooks.insert_dyn.push(hook);
+    }
+
+    fn add_remove_hook_dyn(&mut self, hook: Hook<Dyn>) {
+        self.hooks.remove_dyn.push(hook);
+    }
+
+    fn as_storage_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_storage_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// Marker type for the vector option storage family.
+pub struct VecOptionFamily;
+
+/* ==================== Single Option backend ==================== */
+
+/// Storage for a single optional value of a type.
+pub struct OptionStorage<T, Dyn: ?Sized> {
+    pub data: Option<T>,
+    trait_accessor: TraitAccessor<T, Dyn>,
+    changes: ChangeTracker,
+    hooks: StorageHooks<T, Dyn>,
+}
+impl<T: Clone, Dyn: ?Sized> Clone for OptionStorage<T, Dyn> {
+    fn clone(&self) -> Self {
+        Self {
+            data: self.data.clone(),
+            trait_accessor: self.trait_accessor,
+            changes: self.changes.clone(),
+            hooks: StorageHooks::default(),
+        }
+    }
+}
+
+impl<T, Dyn: ?Sized + DynClone> OptionStorage<T, Dyn> {
+    /// Clone the stored value into a new boxed trait object, leaving the stored value in place.
+    pub fn clone_boxed(&self) -> Option<Box<Dyn>> {
+        self.get_dyn().map(dyn_clone::clone_box)
+    }
+}
+
+impl<T, Dyn: ?Sized> OptionStorage<T, Dyn> {
+    pub fn new(trait_accessor: TraitAccessor<T, Dyn>) -> Self {
+        Self {
+            data: None,
+            trait_accessor,
+            changes: ChangeTracker::default(),
+            hooks: StorageHooks::default(),
+        }
+    }
+
+    /// Call `hook` with index `0` and the value every time a value is set from now on.
+    pub fn on_insert(&mut self, hook: impl Fn(usize, &T) + Send + Sync + 'static) {
+        self.hooks.insert.push(Arc::new(hook));
+    }
+
+    /// Call `hook` with index `0` and the value every time a value is taken or replaced from now on.
+    pub fn on_remove(&mut self, hook: impl Fn(usize, &T) + Send + Sync + 'static) {
+        self.hooks.remove.push(Arc::new(hook));
+    }
+
+    /// Enable or disable change tracking of the single slot, reported at index `0`.
+    ///
+    /// See [`VecOptionStorage::set_change_tracking`] for which operations mark changes.
+    pub fn set_change_tracking(&mut self, enabled: bool) {
+        self.changes.set_enabled(enabled);
+    }
+
+    /// Whether change tracking is enabled.
+    pub fn tracks_changes(&self) -> bool {
+        self.changes.enabled
+    }
+
+    /// Set the tick recorded for subsequent changes. Kept in sync by [`TraitTypeMap::increment_change_tick`].
+    pub fn set_change_tick(&mut self, tick: u64) {
+        self.changes.tick = tick;
+    }
+
+    /// Return the flags of the slot if it changed, and clear them.
+    pub fn drain_changes(&mut self) -> Vec<(usize, ChangeFlags)> {
+        self.changes.drain()
+    }
+
+    /// `[0]` if the slot's latest change happened at or after `tick`, otherwise empty.
+    pub fn changed_since(&self, tick: u64) -> Vec<usize> {
+        self.changes.changed_since(tick)
+    }
+
+    #[inline(always)]
+    pub fn set(&mut self, v: T) {
+        if let Some(old) = &self.data {
+            self.hooks.removed(0, old, self.trait_accessor.up_ref);
+        }
+        let value = self.data.insert(v);
+        self.hooks.inserted(0, value, self.trait_accessor.up_ref);
+        self.changes.record(0, ChangeFlags::ADDED);
+    }
+
+    #[inline(always)]
+    pub fn get(&self) -> Option<&T> {
+        self.data.as_ref()
+    }
+
+    #[inline(always)]
+    pub fn get_mut(&mut self) -> Option<&mut T> {
+        let value = self.data.as_mut();
+        if value.is_some() {
+            self.changes.record(0, ChangeFlags::MODIFIED);
+        }
+        value
+    }
+
+    #[inline(always)]
+    pub fn take(&mut self) -> Option<T> {
+        let value = self.data.take();
+        if let Some(v) = &value {
+            self.hooks.removed(0, v, self.trait_accessor.up_ref);
+            self.changes.record(0, ChangeFlags::REMOVED);
+        }
+        value
+    }
+
+    #[inline(always)]
+    pub fn is_some(&self) -> bool {
+        self.data.is_some()
+    }
+
+    #[inline(always)]
+    pub fn get_dyn(&self) -> Option<&Dyn> {
+        self.get().map(|v| (self.trait_accessor.up_ref)(v))
+    }
+
+    #[inline(always)]
+    pub fn get_dyn_mut(&mut self) -> Option<&mut Dyn> {
+        let up_mut = self.trait_accessor.up_mut;
         self.get_mut().map(up_mut)
     }
 
     #[inline(always)]
-    pub fn take_boxed(&mut self) -> Option<Box<Dyn>> {
-        self.take().map(|v| (self.trait_accessor.up_box)(v))
+    pub fn take_boxed(&mut self) -> Option<Box<Dyn>> {
+        self.take().map(|v| (self.trait_accessor.up_box)(v))
+    }
+}
+
+/// Trait object interface for single-value storage.
+///
+/// This allows accessing the stored value as a trait object without knowing the concrete type.
+pub trait TraitOptionStorage<Dyn: ?Sized>: Any {
+    fn is_some(&self) -> bool;
+    fn get(&self) -> Option<&Dyn>;
+    fn get_mut(&mut self) -> Option<&mut Dyn>;
+    fn take_boxed(&mut self) -> Option<Box<Dyn>>;
+    /// Iterate over the stored value (if any) at index `0`.
+    fn iter_dyn(&self) -> Box<dyn Iterator<Item = (usize, &Dyn)> + '_>;
+    fn iter_dyn_mut(&mut self) -> Box<dyn Iterator<Item = (usize, &mut Dyn)> + '_>;
+    fn storage_type_name(&self) -> &'static str;
+    /// Set the value from a `Box<dyn Any>`, handing it back if it is not of the stored type.
+    /// Returns index `0` on success.
+    fn push_any(&mut self, value: Box<dyn Any>) -> Result<usize, Box<dyn Any>>;
+    fn set_change_tick(&mut self, tick: u64);
+    fn drain_changes(&mut self) -> Vec<(usize, ChangeFlags)>;
+    fn changed_since(&self, tick: u64) -> Vec<usize>;
+    /// Register a callback for every value inserted from now on, as a trait object.
+    fn add_insert_hook_dyn(&mut self, hook: Hook<Dyn>);
+    /// Register a callback for every value removed from now on, as a trait object.
+    fn add_remove_hook_dyn(&mut self, hook: Hook<Dyn>);
+    fn as_storage_any(&self) -> &dyn Any;
+    fn as_storage_any_mut(&mut self) -> &mut dyn Any;
+}
+impl<T: 'static, Dyn: ?Sized + 'static> TraitOptionStorage<Dyn> for OptionStorage<T, Dyn> {
+    fn is_some(&self) -> bool {
+        self.is_some()
+    }
+
+    #[inline]
+    fn get(&self) -> Option<&Dyn> {
+        OptionStorage::<T, Dyn>::get_dyn(self)
+    }
+
+    #[inline]
+    fn get_mut(&mut self) -> Option<&mut Dyn> {
+        OptionStorage::<T, Dyn>::get_dyn_mut(self)
+    }
+
+    #[inline]
+    fn take_boxed(&mut self) -> Option<Box<Dyn>> {
+        OptionStorage::<T, Dyn>::take_boxed(self)
+    }
+
+    fn iter_dyn(&self) -> Box<dyn Iterator<Item = (usize, &Dyn)> + '_> {
+        Box::new(
+            OptionStorage::<T, Dyn>::get_dyn(self)
+                .map(|v| (0, v))
+                .into_iter(),
+        )
+    }
+
+    fn iter_dyn_mut(&mut self) -> Box<dyn Iterator<Item = (usize, &mut Dyn)> + '_> {
+        Box::new(
+            OptionStorage::<T, Dyn>::get_dyn_mut(self)
+                .map(|v| (0, v))
+                .into_iter(),
+        )
+    }
+
+    fn storage_type_name(&self) -> &'static str {
+        type_name::<Self>()
+    }
+
+    fn push_any(&mut self, value: Box<dyn Any>) -> Result<usize, Box<dyn Any>> {
+        value.downcast::<T>().map(|v| {
+            self.set(*v);
+            0
+        })
+    }
+
+    fn set_change_tick(&mut self, tick: u64) {
+        OptionStorage::<T, Dyn>::set_change_tick(self, tick);
+    }
+
+    fn drain_changes(&mut self) -> Vec<(usize, ChangeFlags)> {
+        OptionStorage::<T, Dyn>::drain_changes(self)
+    }
+
+    fn changed_since(&self, tick: u64) -> Vec<usize> {
+        OptionStorage::<T, Dyn>::changed_since(self, tick)
+    }
+
+    fn add_insert_hook_dyn(&mut self, hook: Hook<Dyn>) {
+        self.hooks.insert_dyn.push(hook);
+    }
+
+    fn add_remove_hook_dyn(&mut self, hook: Hook<Dyn>) {
+        self.hooks.remove_dyn.push(hook);
+    }
+
+    fn as_storage_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_storage_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// Marker type for the single-value storage family.
+pub struct OptionFamily;
+
+/* ==================== Sparse set backend ==================== */
+
+/// Storage for multiple values of a single type in a sparse set.
+///
+/// Values are kept contiguous in a dense array, so iteration never visits holes, while a sparse
+/// table maps each key to its position in the dense array. Keys stay valid until their value
+/// is taken, no matter how many other values are inserted or removed; both are O(1).
+///
+/// Keys of taken values are handed out again by `push`, so a key kept after its value was taken
+/// may refer to an unrelated value later. Use generational [`Handle`]s (from [`insert`](Self::insert))
+/// to detect this.
+///
+/// # Examples
+///
+/// ```rust
+/// use trait_type_map::{impl_trait_accessible, SparseSetFamily, TraitTypeMap};
+///
+/// trait Named {
+///     fn name(&self) -> &str;
+/// }
+/// struct Item(&'static str);
+/// impl Named for Item {
+///     fn name(&self) -> &str { self.0 }
+/// }
+/// impl_trait_accessible!(dyn Named; Item);
+///
+/// let mut map: TraitTypeMap<dyn Named, SparseSetFamily> = TraitTypeMap::new();
+/// map.register_type_storage::<Item>();
+/// let storage = map.get_storage_mut::<Item>();
+/// let a = storage.push(Item("a"));
+/// let b = storage.push(Item("b"));
+/// let c = storage.push(Item("c"));
+///
+/// // Taking a value moves the last one into its dense position, but keys are unaffected
+/// assert_eq!(storage.take(a).unwrap().0, "a");
+/// assert_eq!(storage.get(c).unwrap().0, "c");
+/// assert_eq!(storage.as_slice().len(), 2);
+///
+/// // Vacated keys are handed out again, but handles to the old value go stale
+/// let e = storage.insert(Item("e"));
+/// assert_eq!(storage.take(e.index()).unwrap().0, "e");
+/// assert_eq!(storage.push(Item("d")), e.index());
+/// assert!(storage.get_by_handle(e).is_none());
+///
+/// let storage = map.get_trait_storage(std::any::TypeId::of::<Item>()).unwrap();
+/// assert_eq!(storage.get(b).unwrap().name(), "b");
+/// let mut names: Vec<_> = storage.iter_dyn().map(|(key, v)| (key, v.name())).collect();
+/// names.sort();
+/// assert_eq!(names, [(a, "d"), (b, "b"), (c, "c")]);
+/// ```
+pub struct SparseSetStorage<T, Dyn: ?Sized> {
+    dense: Vec<T>,
+    /// Key of each value in `dense`.
+    keys: Vec<usize>,
+    /// Position in `dense` of the value stored under each key.
+    sparse: Vec<Option<usize>>,
+    /// Generation of each key, bumped whenever its value is taken. Same length as `sparse`.
+    generations: Vec<u32>,
+    free: Vec<usize>,
+    trait_accessor: TraitAccessor<T, Dyn>,
+    changes: ChangeTracker,
+    hooks: StorageHooks<T, Dyn>,
+}
+impl<T: Clone, Dyn: ?Sized> Clone for SparseSetStorage<T, Dyn> {
+    fn clone(&self) -> Self {
+        Self {
+            dense: self.dense.clone(),
+            keys: self.keys.clone(),
+            sparse: self.sparse.clone(),
+            generations: self.generations.clone(),
+            free: self.free.clone(),
+            trait_accessor: self.trait_accessor,
+            changes: self.changes.clone(),
+            hooks: StorageHooks::default(),
+        }
+    }
+}
+
+impl<T, Dyn: ?Sized + DynClone> SparseSetStorage<T, Dyn> {
+    /// Clone the value stored under `key` into a new boxed trait object, leaving the stored value in place.
+    pub fn clone_boxed(&self, key: usize) -> Option<Box<Dyn>> {
+        self.get_dyn(key).map(dyn_clone::clone_box)
+    }
+}
+
+impl<T, Dyn: ?Sized> SparseSetStorage<T, Dyn> {
+    pub fn new(trait_accessor: TraitAccessor<T, Dyn>) -> Self {
+        Self {
+            dense: Vec::new(),
+            keys: Vec::new(),
+            sparse: Vec::new(),
+            generations: Vec::new(),
+            free: Vec::new(),
+            trait_accessor,
+            changes: ChangeTracker::default(),
+            hooks: StorageHooks::default(),
+        }
+    }
+
+    /// Build a storage from values laid out by key, where `None` marks an unused key.
+    pub fn from_slots(trait_accessor: TraitAccessor<T, Dyn>, slots: Vec<Option<T>>) -> Self {
+        Self::from_slots_and_generations(trait_accessor, slots, Vec::new())
+    }
+
+    /// Build a storage from values laid out by key and the generations of those keys, so
+    /// handles to them stay valid.
+    ///
+    /// Keys without a generation start at `0`.
+    pub(crate) fn from_slots_and_generations(
+        trait_accessor: TraitAccessor<T, Dyn>,
+        slots: Vec<Option<T>>,
+        mut generations: Vec<u32>,
+    ) -> Self {
+        let mut storage = Self::new(trait_accessor);
+        storage.sparse.reserve(slots.len());
+        for (key, slot) in slots.into_iter().enumerate() {
+            match slot {
+                Some(v) => {
+                    storage.sparse.push(Some(storage.dense.len()));
+                    storage.dense.push(v);
+                    storage.keys.push(key);
+                }
+                None => storage.sparse.push(None),
+            }
+        }
+        // Reversed so that `push` reuses the lowest free key first
+        storage.free = (0..storage.sparse.len())
+            .rev()
+            .filter(|&key| storage.sparse[key].is_none())
+            .collect();
+        generations.resize(storage.sparse.len(), 0);
+        storage.generations = generations;
+        storage
+    }
+
+    /// Generation of every key, as long as the sparse table.
+    #[cfg_attr(not(feature = "serde"), allow(dead_code))]
+    pub(crate) fn generations(&self) -> &[u32] {
+        &self.generations
+    }
+
+    /// Call `hook` with the key and value of every value pushed from now on.
+    pub fn on_insert(&mut self, hook: impl Fn(usize, &T) + Send + Sync + 'static) {
+        self.hooks.insert.push(Arc::new(hook));
+    }
+
+    /// Call `hook` with the key and value of every value taken from now on, before it is returned.
+    pub fn on_remove(&mut self, hook: impl Fn(usize, &T) + Send + Sync + 'static) {
+        self.hooks.remove.push(Arc::new(hook));
+    }
+
+    /// Enable or disable change tracking, reported by key.
+    ///
+    /// See [`VecOptionStorage::set_change_tracking`] for which operations mark changes.
+    pub fn set_change_tracking(&mut self, enabled: bool) {
+        self.changes.set_enabled(enabled);
+    }
+
+    /// Whether change tracking is enabled.
+    pub fn tracks_changes(&self) -> bool {
+        self.changes.enabled
+    }
+
+    /// Set the tick recorded for subsequent changes. Kept in sync by [`TraitTypeMap::increment_change_tick`].
+    pub fn set_change_tick(&mut self, tick: u64) {
+        self.changes.tick = tick;
+    }
+
+    /// Return the flags of every changed key, and clear them.
+    pub fn drain_changes(&mut self) -> Vec<(usize, ChangeFlags)> {
+        self.changes.drain()
+    }
+
+    /// Keys whose latest change happened at or after `tick`.
+    pub fn changed_since(&self, tick: u64) -> Vec<usize> {
+        self.changes.changed_since(tick)
+    }
+
+    /// Insert a value, returning its key.
+    ///
+    /// Keys of taken values are reused, so don't keep a key after taking its value; use
+    /// [`insert`](Self::insert) for a handle that detects this.
+    pub fn push(&mut self, v: T) -> usize {
+        let key = self.free.pop().unwrap_or_else(|| {
+            self.sparse.push(None);
+            self.generations.push(0);
+            self.sparse.len() - 1
+        });
+        let pos = self.dense.len();
+        self.sparse[key] = Some(pos);
+        self.dense.push(v);
+        self.keys.push(key);
+        self.hooks
+            .inserted(key, &self.dense[pos], self.trait_accessor.up_ref);
+        self.changes.record(key, ChangeFlags::ADDED);
+        key
+    }
+
+    /// Remove and return the value stored under `key`, moving the last dense value into its place.
+    ///
+    /// The key is handed out again by a later `push`, and handles to the taken value go stale.
+    pub fn take(&mut self, key: usize) -> Option<T> {
+        let pos = self.sparse.get_mut(key)?.take()?;
+        let removed = self.dense.swap_remove(pos);
+        self.keys.swap_remove(pos);
+        if let Some(&moved) = self.keys.get(pos) {
+            self.sparse[moved] = Some(pos);
+        }
+        self.generations[key] = self.generations[key].wrapping_add(1);
+        self.free.push(key);
+        self.hooks
+            .removed(key, &removed, self.trait_accessor.up_ref);
+        self.changes.record(key, ChangeFlags::REMOVED);
+        Some(removed)
+    }
+
+    /// Insert a value and return a generational handle to it.
+    #[inline]
+    pub fn insert(&mut self, v: T) -> Handle<T> {
+        let index = self.push(v);
+        Handle {
+            index,
+            generation: self.generations[index],
+            _marker: PhantomData,
+        }
+    }
+
+    /// Handle to the value currently stored under `key`, if any.
+    #[inline]
+    pub fn handle(&self, key: usize) -> Option<Handle<T>> {
+        self.dense_index(key)?;
+        Some(Handle {
+            index: key,
+            generation: self.generations[key],
+            _marker: PhantomData,
+        })
+    }
+
+    /// Whether `handle` still refers to a live value.
+    #[inline]
+    pub fn contains_handle(&self, handle: Handle<T>) -> bool {
+        self.get_by_handle(handle).is_some()
+    }
+
+    /// Get the value `handle` refers to, or `None` if the handle is stale.
+    #[inline]
+    pub fn get_by_handle(&self, handle: Handle<T>) -> Option<&T> {
+        if self.is_current(handle) {
+            self.get(handle.index)
+        } else {
+            None
+        }
+    }
+
+    /// Mutable counterpart of [`get_by_handle`](Self::get_by_handle).
+    #[inline]
+    pub fn get_mut_by_handle(&mut self, handle: Handle<T>) -> Option<&mut T> {
+        if self.is_current(handle) {
+            self.get_mut(handle.index)
+        } else {
+            None
+        }
+    }
+
+    /// Remove and return the value `handle` refers to, or `None` if the handle is stale.
+    #[inline]
+    pub fn remove(&mut self, handle: Handle<T>) -> Option<T> {
+        if self.is_current(handle) {
+            self.take(handle.index)
+        } else {
+            None
+        }
+    }
+
+    #[inline(always)]
+    fn is_current(&self, handle: Handle<T>) -> bool {
+        self.generations.get(handle.index) == Some(&handle.generation)
+    }
+
+    #[inline]
+    pub fn contains(&self, key: usize) -> bool {
+        self.dense_index(key).is_some()
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.dense.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.dense.is_empty()
+    }
+
+    #[inline(always)]
+    fn dense_index(&self, key: usize) -> Option<usize> {
+        self.sparse.get(key).copied().flatten()
+    }
+
+    #[inline]
+    pub fn get(&self, key: usize) -> Option<&T> {
+        self.dense_index(key).map(|pos| &self.dense[pos])
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self, key: usize) -> Option<&mut T> {
+        let pos = self.dense_index(key)?;
+        self.changes.record(key, ChangeFlags::MODIFIED);
+        Some(&mut self.dense[pos])
+    }
+
+    #[inline]
+    pub fn get_dyn(&self, key: usize) -> Option<&Dyn> {
+        self.get(key).map(|v| (self.trait_accessor.up_ref)(v))
+    }
+
+    #[inline]
+    pub fn get_dyn_mut(&mut self, key: usize) -> Option<&mut Dyn> {
+        let up_mut = self.trait_accessor.up_mut;
+        self.get_mut(key).map(up_mut)
+    }
+
+    #[inline]
+    pub fn take_boxed(&mut self, key: usize) -> Option<Box<Dyn>> {
+        self.take(key).map(|v| (self.trait_accessor.up_box)(v))
+    }
+
+    /// All stored values in dense order. The key of `as_slice()[i]` is `keys()[i]`.
+    #[inline]
+    pub fn as_slice(&self) -> &[T] {
+        &self.dense
+    }
+
+    /// Keys of all stored values, in dense order.
+    #[inline]
+    pub fn keys(&self) -> &[usize] {
+        &self.keys
+    }
+
+    /// Iterate over all stored values in dense order.
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.dense.iter()
+    }
+
+    /// Iterate mutably over all stored values in dense order.
+    ///
+    /// With change tracking enabled, every key is marked [`MODIFIED`](ChangeFlags::MODIFIED).
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.iter_indexed_mut().map(|(_, v)| v)
+    }
+
+    /// Iterate over all stored values along with their keys, in dense order.
+    #[inline]
+    pub fn iter_indexed(&self) -> impl Iterator<Item = (usize, &T)> {
+        self.keys.iter().copied().zip(&self.dense)
+    }
+
+    /// Mutable access to the dense array, marking every key [`MODIFIED`](ChangeFlags::MODIFIED).
+    #[cfg_attr(not(feature = "rayon"), allow(dead_code))]
+    pub(crate) fn as_mut_slice_tracked(&mut self) -> &mut [T] {
+        for &key in &self.keys {
+            self.changes.record(key, ChangeFlags::MODIFIED);
+        }
+        &mut self.dense
+    }
+
+    /// Mutable counterpart of [`iter_indexed`](Self::iter_indexed).
+    ///
+    /// With change tracking enabled, every key is marked [`MODIFIED`](ChangeFlags::MODIFIED).
+    pub fn iter_indexed_mut(&mut self) -> impl Iterator<Item = (usize, &mut T)> {
+        for &key in &self.keys {
+            self.changes.record(key, ChangeFlags::MODIFIED);
+        }
+        self.keys.iter().copied().zip(&mut self.dense)
+    }
+
+    /// Iterate over all stored values as trait objects, along with their keys.
+    pub fn iter_dyn(&self) -> impl Iterator<Item = (usize, &Dyn)> {
+        let up_ref = self.trait_accessor.up_ref;
+        self.iter_indexed().map(move |(key, v)| (key, up_ref(v)))
+    }
+
+    pub fn iter_dyn_mut(&mut self) -> impl Iterator<Item = (usize, &mut Dyn)> {
+        let up_mut = self.trait_accessor.up_mut;
+        self.iter_indexed_mut()
+            .map(move |(key, v)| (key, up_mut(v)))
+    }
+
+    /// Keys of all stored values, in dense order.
+    pub fn indices(&self) -> impl Iterator<Item = usize> + '_ {
+        self.keys.iter().copied()
+    }
+}
+
+/// Trait object interface for sparse set storage.
+///
+/// This allows accessing stored values as trait objects without knowing the concrete type.
+pub trait TraitSparseSetStorage<Dyn: ?Sized>: Any {
+    fn len(&self) -> usize;
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    fn contains(&self, key: usize) -> bool;
+    fn get(&self, key: usize) -> Option<&Dyn>;
+    fn get_mut(&mut self, key: usize) -> Option<&mut Dyn>;
+    fn take_boxed(&mut self, key: usize) -> Option<Box<Dyn>>;
+    /// Iterate over all stored values along with their keys, in dense order.
+    fn iter_dyn(&self) -> Box<dyn Iterator<Item = (usize, &Dyn)> + '_>;
+    fn iter_dyn_mut(&mut self) -> Box<dyn Iterator<Item = (usize, &mut Dyn)> + '_>;
+    /// Iterate over the keys of all stored values, in dense order.
+    fn indices(&self) -> Box<dyn Iterator<Item = usize> + '_>;
+    fn storage_type_name(&self) -> &'static str;
+    /// Push a value given as `Box<dyn Any>`, handing it back if it is not of the stored type.
+    fn push_any(&mut self, value: Box<dyn Any>) -> Result<usize, Box<dyn Any>>;
+    fn set_change_tick(&mut self, tick: u64);
+    fn drain_changes(&mut self) -> Vec<(usize, ChangeFlags)>;
+    fn changed_since(&self, tick: u64) -> Vec<usize>;
+    /// Register a callback for every value inserted from now on, as a trait object.
+    fn add_insert_hook_dyn(&mut self, hook: Hook<Dyn>);
+    /// Register a callback for every value removed from now on, as a trait object.
+    fn add_remove_hook_dyn(&mut self, hook: Hook<Dyn>);
+    fn as_storage_any(&self) -> &dyn Any;
+    fn as_storage_any_mut(&mut self) -> &mut dyn Any;
+}
+impl<T: 'static, Dyn: ?Sized + 'static> TraitSparseSetStorage<Dyn> for SparseSetStorage<T, Dyn> {
+    fn len(&self) -> usize {
+        self.dense.len()
+    }
+
+    fn contains(&self, key: usize) -> bool {
+        SparseSetStorage::<T, Dyn>::contains(self, key)
+    }
+
+    #[inline]
+    fn get(&self, key: usize) -> Option<&Dyn> {
+        SparseSetStorage::<T, Dyn>::get_dyn(self, key)
+    }
+
+    #[inline]
+    fn get_mut(&mut self, key: usize) -> Option<&mut Dyn> {
+        SparseSetStorage::<T, Dyn>::get_dyn_mut(self, key)
+    }
+
+    #[inline]
+    fn take_boxed(&mut self, key: usize) -> Option<Box<Dyn>> {
+        SparseSetStorage::<T, Dyn>::take_boxed(self, key)
+    }
+
+    fn iter_dyn(&self) -> Box<dyn Iterator<Item = (usize, &Dyn)> + '_> {
+        Box::new(SparseSetStorage::<T, Dyn>::iter_dyn(self))
+    }
+
+    fn iter_dyn_mut(&mut self) -> Box<dyn Iterator<Item = (usize, &mut Dyn)> + '_> {
+        Box::new(SparseSetStorage::<T, Dyn>::iter_dyn_mut(self))
+    }
+
+    fn indices(&self) -> Box<dyn Iterator<Item = usize> + '_> {
+        Box::new(SparseSetStorage::<T, Dyn>::indices(self))
+    }
+
+    fn storage_type_name(&self) -> &'static str {
+        type_name::<Self>()
+    }
+
+    fn push_any(&mut self, value: Box<dyn Any>) -> Result<usize, Box<dyn Any>> {
+        value.downcast::<T>().map(|v| self.push(*v))
+    }
+
+    fn set_change_tick(&mut self, tick: u64) {
+        SparseSetStorage::<T, Dyn>::set_change_tick(self, tick);
+    }
+
+    fn drain_changes(&mut self) -> Vec<(usize, ChangeFlags)> {
+        SparseSetStorage::<T, Dyn>::drain_changes(self)
+    }
+
+    fn changed_since(&self, tick: u64) -> Vec<usize> {
+        SparseSetStorage::<T, Dyn>::changed_since(self, tick)
+    }
+
+    fn add_insert_hook_dyn(&mut self, hook: Hook<Dyn>) {
+        self.hooks.insert_dyn.push(hook);
+    }
+
+    fn add_remove_hook_dyn(&mut self, hook: Hook<Dyn>) {
+        self.hooks.remove_dyn.push(hook);
+    }
+
+    fn as_storage_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_storage_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// Marker type for the sparse set storage family.
+pub struct SparseSetFamily;
+
+/* ==================== Keyed backend ==================== */
+
+/// Storage for multiple values of a single type, looked up by user-supplied keys of type `K`.
+///
+/// Values are kept in insertion order, and each one also has a position `0..len` in that order,
+/// which is what index-based APIs (such as [`TraitTypeMap::iter_dyn`] and hooks) report.
+/// Removing a value shifts the positions of all values after it down by one, keeping the rest
+/// in insertion order; keys are never affected.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::any::TypeId;
+/// use trait_type_map::{impl_trait_accessible, HashMapFamily, TraitTypeMap};
+///
+/// trait Plugin {
+///     fn version(&self) -> u32;
+/// }
+/// struct Exporter(u32);
+/// impl Plugin for Exporter {
+///     fn version(&self) -> u32 { self.0 }
+/// }
+/// impl_trait_accessible!(dyn Plugin; Exporter);
+///
+/// let mut map: TraitTypeMap<dyn Plugin, HashMapFamily<String>> = TraitTypeMap::new();
+/// map.register_type_storage::<Exporter>();
+/// let storage = map.get_storage_mut::<Exporter>();
+/// assert!(storage.insert("csv".to_string(), Exporter(1)).is_none());
+/// storage.insert("json".to_string(), Exporter(2));
+/// storage.insert("xml".to_string(), Exporter(4));
+///
+/// // Inserting under an existing key replaces the value and returns the old one
+/// assert_eq!(storage.insert("csv".to_string(), Exporter(3)).unwrap().0, 1);
+/// assert_eq!(storage.get("csv").unwrap().0, 3);
+/// assert_eq!(storage.get_dyn("json").unwrap().version(), 2);
+///
+/// let storage = map.get_trait_storage_mut(TypeId::of::<Exporter>()).unwrap();
+/// assert_eq!(storage.get(&"json".to_string()).unwrap().version(), 2);
+/// assert!(storage.take_boxed(&"csv".to_string()).is_some());
+/// assert_eq!(storage.keys().collect::<Vec<_>>(), ["json", "xml"]);
+/// ```
+pub struct KeyedStorage<K, T, Dyn: ?Sized> {
+    data: IndexMap<K, T, DefaultHashBuilder>,
+    trait_accessor: TraitAccessor<T, Dyn>,
+    hooks: StorageHooks<T, Dyn>,
+}
+impl<K: Clone, T: Clone, Dyn: ?Sized> Clone for KeyedStorage<K, T, Dyn> {
+    fn clone(&self) -> Self {
+        Self {
+            data: self.data.clone(),
+            trait_accessor: self.trait_accessor,
+            hooks: StorageHooks::default(),
+        }
+    }
+}
+
+impl<K: Hash + Eq, T, Dyn: ?Sized + DynClone> KeyedStorage<K, T, Dyn> {
+    /// Clone the value stored under `key` into a new boxed trait object, leaving the stored value in place.
+    pub fn clone_boxed<Q>(&self, key: &Q) -> Option<Box<Dyn>>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.get_dyn(key).map(dyn_clone::clone_box)
+    }
+}
+
+impl<K, T, Dyn: ?Sized> KeyedStorage<K, T, Dyn> {
+    pub fn new(trait_accessor: TraitAccessor<T, Dyn>) -> Self {
+        Self {
+            data: IndexMap::default(),
+            trait_accessor,
+            hooks: StorageHooks::default(),
+        }
+    }
+
+    #[cfg_attr(not(feature = "serde"), allow(dead_code))]
+    pub(crate) fn from_map(
+        trait_accessor: TraitAccessor<T, Dyn>,
+        data: IndexMap<K, T, DefaultHashBuilder>,
+    ) -> Self {
+        Self {
+            data,
+            trait_accessor,
+            hooks: StorageHooks::default(),
+        }
+    }
+
+    /// Call `hook` with the position and value of every value inserted from now on.
+    pub fn on_insert(&mut self, hook: impl Fn(usize, &T) + Send + Sync + 'static) {
+        self.hooks.insert.push(Arc::new(hook));
+    }
+
+    /// Call `hook` with the position and value of every value removed or replaced from now on.
+    pub fn on_remove(&mut self, hook: impl Fn(usize, &T) + Send + Sync + 'static) {
+        self.hooks.remove.push(Arc::new(hook));
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Key and value at position `i`.
+    pub fn get_index(&self, i: usize) -> Option<(&K, &T)> {
+        self.data.get_index(i)
+    }
+
+    /// Iterate over all keys in insertion order.
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.data.keys()
+    }
+
+    /// Iterate over all keys and values in insertion order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &T)> {
+        self.data.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&K, &mut T)> {
+        self.data.iter_mut()
+    }
+
+    /// Iterate over all stored values along with their positions.
+    pub fn iter_indexed(&self) -> impl Iterator<Item = (usize, &T)> {
+        self.data.values().enumerate()
+    }
+
+    pub fn iter_indexed_mut(&mut self) -> impl Iterator<Item = (usize, &mut T)> {
+        self.data.values_mut().enumerate()
+    }
+
+    /// Iterate over all stored values as trait objects, along with their positions.
+    pub fn iter_dyn(&self) -> impl Iterator<Item = (usize, &Dyn)> {
+        let up_ref = self.trait_accessor.up_ref;
+        self.iter_indexed().map(move |(i, v)| (i, up_ref(v)))
+    }
+
+    pub fn iter_dyn_mut(&mut self) -> impl Iterator<Item = (usize, &mut Dyn)> {
+        let up_mut = self.trait_accessor.up_mut;
+        self.iter_indexed_mut().map(move |(i, v)| (i, up_mut(v)))
+    }
+
+    /// Positions of all stored values, `0..len`.
+    pub fn indices(&self) -> std::ops::Range<usize> {
+        0..self.data.len()
+    }
+}
+
+impl<K: Hash + Eq, T, Dyn: ?Sized> KeyedStorage<K, T, Dyn> {
+    /// Insert a value under `key`, returning the value previously stored under it.
+    ///
+    /// A replaced value keeps its position and is reported to remove hooks before the new
+    /// value is reported to insert hooks.
+    pub fn insert(&mut self, key: K, v: T) -> Option<T> {
+        let (i, old) = self.data.insert_full(key, v);
+        if let Some(old) = &old {
+            self.hooks.removed(i, old, self.trait_accessor.up_ref);
+        }
+        self.hooks
+            .inserted(i, &self.data[i], self.trait_accessor.up_ref);
+        old
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.data.contains_key(key)
+    }
+
+    /// Position of the value stored under `key`.
+    pub fn position<Q>(&self, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.data.get_index_of(key)
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&T>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.data.get(key)
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut T>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.data.get_mut(key)
+    }
+
+    pub fn get_dyn<Q>(&self, key: &Q) -> Option<&Dyn>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.get(key).map(|v| (self.trait_accessor.up_ref)(v))
+    }
+
+    pub fn get_dyn_mut<Q>(&mut self, key: &Q) -> Option<&mut Dyn>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let up_mut = self.trait_accessor.up_mut;
+        self.get_mut(key).map(up_mut)
+    }
+
+    /// Remove and return the value stored under `key`, shifting all values after it down by one position.
+    ///
+    /// This is O(n) in the number of values after it, to keep the rest in insertion order.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<T>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let (i, _, removed) = self.data.shift_remove_full(key)?;
+        self.hooks.removed(i, &removed, self.trait_accessor.up_ref);
+        Some(removed)
+    }
+
+    pub fn take_boxed<Q>(&mut self, key: &Q) -> Option<Box<Dyn>>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.remove(key).map(|v| (self.trait_accessor.up_box)(v))
+    }
+}
+
+/// Trait object interface for keyed storage.
+///
+/// This allows accessing stored values as trait objects without knowing the concrete type.
+pub trait TraitKeyedStorage<Dyn: ?Sized, K>: Any {
+    fn len(&self) -> usize;
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    fn contains_key(&self, key: &K) -> bool;
+    fn get(&self, key: &K) -> Option<&Dyn>;
+    fn get_mut(&mut self, key: &K) -> Option<&mut Dyn>;
+    fn take_boxed(&mut self, key: &K) -> Option<Box<Dyn>>;
+    /// Insert a value given as `Box<dyn Any>` under `key`, handing it back if it is not of the
+    /// stored type. Returns the value previously stored under `key`.
+    fn insert_any(&mut self, key: K, value: Box<dyn Any>)
+        -> Result<Option<Box<Dyn>>, Box<dyn Any>>;
+    /// Iterate over all keys in insertion order.
+    fn keys(&self) -> Box<dyn Iterator<Item = &K> + '_>;
+    /// Iterate over all stored values along with their positions.
+    fn iter_dyn(&self) -> Box<dyn Iterator<Item = (usize, &Dyn)> + '_>;
+    fn iter_dyn_mut(&mut self) -> Box<dyn Iterator<Item = (usize, &mut Dyn)> + '_>;
+    /// Iterate over the positions of all stored values.
+    fn indices(&self) -> Box<dyn Iterator<Item = usize> + '_>;
+    fn storage_type_name(&self) -> &'static str;
+    /// Values need a key, so this always hands the value back; use
+    /// [`insert_any`](Self::insert_any) instead.
+    fn push_any(&mut self, value: Box<dyn Any>) -> Result<usize, Box<dyn Any>>;
+    /// Register a callback for every value inserted from now on, as a trait object.
+    fn add_insert_hook_dyn(&mut self, hook: Hook<Dyn>);
+    /// Register a callback for every value removed from now on, as a trait object.
+    fn add_remove_hook_dyn(&mut self, hook: Hook<Dyn>);
+    fn as_storage_any(&self) -> &dyn Any;
+    fn as_storage_any_mut(&mut self) -> &mut dyn Any;
+}
+impl<K: Hash + Eq + 'static, T: 'static, Dyn: ?Sized + 'static> TraitKeyedStorage<Dyn, K>
+    for KeyedStorage<K, T, Dyn>
+{
+    fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    fn contains_key(&self, key: &K) -> bool {
+        self.data.contains_key(key)
+    }
+
+    #[inline]
+    fn get(&self, key: &K) -> Option<&Dyn> {
+        KeyedStorage::<K, T, Dyn>::get_dyn(self, key)
+    }
+
+    #[inline]
+    fn get_mut(&mut self, key: &K) -> Option<&mut Dyn> {
+        KeyedStorage::<K, T, Dyn>::get_dyn_mut(self, key)
+    }
+
+    #[inline]
+    fn take_boxed(&mut self, key: &K) -> Option<Box<Dyn>> {
+        KeyedStorage::<K, T, Dyn>::take_boxed(self, key)
+    }
+
+    fn insert_any(
+        &mut self,
+        key: K,
+        value: Box<dyn Any>,
+    ) -> Result<Option<Box<Dyn>>, Box<dyn Any>> {
+        let value = value.downcast::<T>()?;
+        let up_box = self.trait_accessor.up_box;
+        Ok(self.insert(key, *value).map(up_box))
+    }
+
+    fn keys(&self) -> Box<dyn Iterator<Item = &K> + '_> {
+        Box::new(self.data.keys())
+    }
+
+    fn iter_dyn(&self) -> Box<dyn Iterator<Item = (usize, &Dyn)> + '_> {
+        Box::new(KeyedStorage::<K, T, Dyn>::iter_dyn(self))
+    }
+
+    fn iter_dyn_mut(&mut self) -> Box<dyn Iterator<Item = (usize, &mut Dyn)> + '_> {
+        Box::new(KeyedStorage::<K, T, Dyn>::iter_dyn_mut(self))
+    }
+
+    fn indices(&self) -> Box<dyn Iterator<Item = usize> + '_> {
+        Box::new(KeyedStorage::<K, T, Dyn>::indices(self))
+    }
+
+    fn storage_type_name(&self) -> &'static str {
+        type_name::<Self>()
+    }
+
+    fn push_any(&mut self, value: Box<dyn Any>) -> Result<usize, Box<dyn Any>> {
+        Err(value)
+    }
+
+    fn add_insert_hook_dyn(&mut self, hook: Hook<Dyn>) {
+        self.hooks.insert_dyn.push(hook);
+    }
+
+    fn add_remove_hook_dyn(&mut self, hook: Hook<Dyn>) {
+        self.hooks.remove_dyn.push(hook);
+    }
+
+    fn as_storage_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_storage_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// Marker type for the keyed storage family, storing values under keys of type `K`.
+///
+/// Keys must be `Hash + Eq + Clone`; cloning is needed so registered `Clone` types can be cloned with the map.
+///
+/// Since values need a key, [`TraitTypeMap::insert_boxed`] always hands values back for this family.
+///
+/// # Examples
+///
+/// ```rust
+/// use trait_type_map::{impl_trait_accessible, AsAny, HashMapFamily, TraitTypeMap};
+///
+/// trait Plugin: AsAny {}
+/// struct Exporter;
+/// impl Plugin for Exporter {}
+/// impl_trait_accessible!(dyn Plugin; Exporter);
+///
+/// let mut map: TraitTypeMap<dyn Plugin, HashMapFamily<String>> = TraitTypeMap::new();
+/// map.register_type_storage::<Exporter>();
+/// assert!(map.insert_boxed(Box::new(Exporter)).is_err());
+/// assert_eq!(map.get_storage::<Exporter>().len(), 0);
+/// ```
+pub struct HashMapFamily<K>(PhantomData<fn() -> K>);
+
+/* ==================== Ordered backend ==================== */
+
+/// Storage for multiple values of a single type, kept sorted by a priority of type `P`.
+///
+/// Values with equal priority keep their insertion order. Positions `0..len` follow the sorted
+/// order, so inserting or removing a value shifts the positions of all values after it.
+/// For descending order, use [`std::cmp::Reverse`] as the priority.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::cmp::Reverse;
+/// use trait_type_map::{impl_trait_accessible, OrderedFamily, TraitTypeMap};
+///
+/// trait Named {
+///     fn name(&self) -> &str;
+/// }
+/// struct Item(&'static str);
+/// impl Named for Item {
+///     fn name(&self) -> &str { self.0 }
+/// }
+/// impl_trait_accessible!(dyn Named; Item);
+///
+/// let mut map: TraitTypeMap<dyn Named, OrderedFamily<Reverse<u32>>> = TraitTypeMap::new();
+/// map.register_type_storage::<Item>();
+/// let storage = map.get_storage_mut::<Item>();
+/// storage.insert(Reverse(1), Item("low"));
+/// storage.insert(Reverse(5), Item("high"));
+/// storage.insert(Reverse(1), Item("low, later"));
+///
+/// let names: Vec<_> = storage.iter().map(|(_, item)| item.0).collect();
+/// assert_eq!(names, ["high", "low", "low, later"]);
+///
+/// // Changing a priority moves the value to its new position
+/// assert_eq!(storage.set_priority(2, Reverse(9)), Some(0));
+/// assert_eq!(storage.get_dyn(0).unwrap().name(), "low, later");
+/// ```
+pub struct OrderedStorage<P, T, Dyn: ?Sized> {
+    data: Vec<(P, T)>,
+    trait_accessor: TraitAccessor<T, Dyn>,
+    hooks: StorageHooks<T, Dyn>,
+}
+impl<P: Clone, T: Clone, Dyn: ?Sized> Clone for OrderedStorage<P, T, Dyn> {
+    fn clone(&self) -> Self {
+        Self {
+            data: self.data.clone(),
+            trait_accessor: self.trait_accessor,
+            hooks: StorageHooks::default(),
+        }
+    }
+}
+
+impl<P, T, Dyn: ?Sized + DynClone> OrderedStorage<P, T, Dyn> {
+    /// Clone the value at position `i` into a new boxed trait object, leaving the stored value in place.
+    pub fn clone_boxed(&self, i: usize) -> Option<Box<Dyn>> {
+        self.get_dyn(i).map(dyn_clone::clone_box)
+    }
+}
+
+impl<P, T, Dyn: ?Sized> OrderedStorage<P, T, Dyn> {
+    pub fn new(trait_accessor: TraitAccessor<T, Dyn>) -> Self {
+        Self {
+            data: Vec::new(),
+            trait_accessor,
+            hooks: StorageHooks::default(),
+        }
+    }
+
+    /// Call `hook` with the position and value of every value inserted from now on.
+    pub fn on_insert(&mut self, hook: impl Fn(usize, &T) + Send + Sync + 'static) {
+        self.hooks.insert.push(Arc::new(hook));
+    }
+
+    /// Call `hook` with the position and value of every value removed from now on, before it is returned.
+    pub fn on_remove(&mut self, hook: impl Fn(usize, &T) + Send + Sync + 'static) {
+        self.hooks.remove.push(Arc::new(hook));
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    #[inline]
+    pub fn get(&self, i: usize) -> Option<&T> {
+        self.data.get(i).map(|(_, v)| v)
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self, i: usize) -> Option<&mut T> {
+        self.data.get_mut(i).map(|(_, v)| v)
+    }
+
+    /// Priority of the value at position `i`.
+    #[inline]
+    pub fn priority(&self, i: usize) -> Option<&P> {
+        self.data.get(i).map(|(p, _)| p)
+    }
+
+    #[inline]
+    pub fn get_dyn(&self, i: usize) -> Option<&Dyn> {
+        self.get(i).map(|v| (self.trait_accessor.up_ref)(v))
+    }
+
+    #[inline]
+    pub fn get_dyn_mut(&mut self, i: usize) -> Option<&mut Dyn> {
+        let up_mut = self.trait_accessor.up_mut;
+        self.get_mut(i).map(up_mut)
+    }
+
+    /// Remove and return the value at position `i` along with its priority, keeping the rest in order.
+    pub fn remove(&mut self, i: usize) -> Option<(P, T)> {
+        if i >= self.data.len() {
+            return None;
+        }
+        let removed = self.data.remove(i);
+        self.hooks
+            .removed(i, &removed.1, self.trait_accessor.up_ref);
+        Some(removed)
+    }
+
+    pub fn take_boxed(&mut self, i: usize) -> Option<Box<Dyn>> {
+        self.remove(i).map(|(_, v)| (self.trait_accessor.up_box)(v))
+    }
+
+    /// Iterate over all priorities and values in order.
+    pub fn iter(&self) -> impl Iterator<Item = (&P, &T)> {
+        self.data.iter().map(|(p, v)| (p, v))
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&P, &mut T)> {
+        self.data.iter_mut().map(|(p, v)| (&*p, v))
+    }
+
+    /// Iterate over all stored values along with their positions, in order.
+    pub fn iter_indexed(&self) -> impl Iterator<Item = (usize, &T)> {
+        self.data.iter().map(|(_, v)| v).enumerate()
+    }
+
+    pub fn iter_indexed_mut(&mut self) -> impl Iterator<Item = (usize, &mut T)> {
+        self.data.iter_mut().map(|(_, v)| v).enumerate()
+    }
+
+    /// Iterate over all stored values as trait objects, along with their positions.
+    pub fn iter_dyn(&self) -> impl Iterator<Item = (usize, &Dyn)> {
+        let up_ref = self.trait_accessor.up_ref;
+        self.iter_indexed().map(move |(i, v)| (i, up_ref(v)))
+    }
+
+    pub fn iter_dyn_mut(&mut self) -> impl Iterator<Item = (usize, &mut Dyn)> {
+        let up_mut = self.trait_accessor.up_mut;
+        self.iter_indexed_mut().map(move |(i, v)| (i, up_mut(v)))
+    }
+
+    /// Positions of all stored values, `0..len`.
+    pub fn indices(&self) -> std::ops::Range<usize> {
+        0..self.data.len()
+    }
+}
+
+impl<P: Ord, T, Dyn: ?Sized> OrderedStorage<P, T, Dyn> {
+    /// Build a storage from priority and value pairs in any order.
+    #[cfg_attr(not(feature = "serde"), allow(dead_code))]
+    pub(crate) fn from_entries(
+        trait_accessor: TraitAccessor<T, Dyn>,
+        mut data: Vec<(P, T)>,
+    ) -> Self {
+        data.sort_by(|a, b| a.0.cmp(&b.0));
+        Self {
+            data,
+            trait_accessor,
+            hooks: StorageHooks::default(),
+        }
+    }
+
+    /// Insert a value after all values with a lower or equal priority, returning its position.
+    pub fn insert(&mut self, priority: P, v: T) -> usize {
+        let i = self.data.partition_point(|(p, _)| *p <= priority);
+        self.data.insert(i, (priority, v));
+        self.hooks
+            .inserted(i, &self.data[i].1, self.trait_accessor.up_ref);
+        i
+    }
+
+    /// Change the priority of the value at position `i`, returning its new position.
+    ///
+    /// The value is placed after all other values with a lower or equal priority. Hooks are not called.
+    pub fn set_priority(&mut self, i: usize, priority: P) -> Option<usize> {
+        if i >= self.data.len() {
+            return None;
+        }
+        let (_, v) = self.data.remove(i);
+        let new = self.data.partition_point(|(p, _)| *p <= priority);
+        self.data.insert(new, (priority, v));
+        Some(new)
+    }
+}
+
+/// Trait object interface for ordered storage.
+///
+/// This allows accessing stored values as trait objects without knowing the concrete type.
+pub trait TraitOrderedStorage<Dyn: ?Sized, P>: Any {
+    fn len(&self) -> usize;
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    fn get(&self, idx: usize) -> Option<&Dyn>;
+    fn get_mut(&mut self, idx: usize) -> Option<&mut Dyn>;
+    fn priority(&self, idx: usize) -> Option<&P>;
+    fn take_boxed(&mut self, idx: usize) -> Option<Box<Dyn>>;
+    /// Insert a value given as `Box<dyn Any>` with `priority`, handing it back if it is not of
+    /// the stored type. Returns its position.
+    fn insert_any(&mut self, priority: P, value: Box<dyn Any>) -> Result<usize, Box<dyn Any>>;
+    /// Iterate over all priorities and values in order.
+    fn iter_prioritized(&self) -> Box<dyn Iterator<Item = (&P, &Dyn)> + '_>;
+    fn iter_prioritized_mut(&mut self) -> Box<dyn Iterator<Item = (&P, &mut Dyn)> + '_>;
+    /// Iterate over all stored values along with their positions, in order.
+    fn iter_dyn(&self) -> Box<dyn Iterator<Item = (usize, &Dyn)> + '_>;
+    fn iter_dyn_mut(&mut self) -> Box<dyn Iterator<Item = (usize, &mut Dyn)> + '_>;
+    /// Iterate over the positions of all stored values.
+    fn indices(&self) -> Box<dyn Iterator<Item = usize> + '_>;
+    fn storage_type_name(&self) -> &'static str;
+    /// Values need a priority, so this always hands the value back; use
+    /// [`insert_any`](Self::insert_any) instead.
+    fn push_any(&mut self, value: Box<dyn Any>) -> Result<usize, Box<dyn Any>>;
+    /// Register a callback for every value inserted from now on, as a trait object.
+    fn add_insert_hook_dyn(&mut self, hook: Hook<Dyn>);
+    /// Register a callback for every value removed from now on, as a trait object.
+    fn add_remove_hook_dyn(&mut self, hook: Hook<Dyn>);
+    fn as_storage_any(&self) -> &dyn Any;
+    fn as_storage_any_mut(&mut self) -> &mut dyn Any;
+}
+impl<P: Ord + 'static, T: 'static, Dyn: ?Sized + 'static> TraitOrderedStorage<Dyn, P>
+    for OrderedStorage<P, T, Dyn>
+{
+    fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    #[inline]
+    fn get(&self, idx: usize) -> Option<&Dyn> {
+        OrderedStorage::<P, T, Dyn>::get_dyn(self, idx)
+    }
+
+    #[inline]
+    fn get_mut(&mut self, idx: usize) -> Option<&mut Dyn> {
+        OrderedStorage::<P, T, Dyn>::get_dyn_mut(self, idx)
+    }
+
+    fn priority(&self, idx: usize) -> Option<&P> {
+        OrderedStorage::<P, T, Dyn>::priority(self, idx)
+    }
+
+    #[inline]
+    fn take_boxed(&mut self, idx: usize) -> Option<Box<Dyn>> {
+        OrderedStorage::<P, T, Dyn>::take_boxed(self, idx)
+    }
+
+    fn insert_any(&mut self, priority: P, value: Box<dyn Any>) -> Result<usize, Box<dyn Any>> {
+        value.downcast::<T>().map(|v| self.insert(priority, *v))
+    }
+
+    fn iter_prioritized(&self) -> Box<dyn Iterator<Item = (&P, &Dyn)> + '_> {
+        let up_ref = self.trait_accessor.up_ref;
+        Box::new(self.iter().map(move |(p, v)| (p, up_ref(v))))
+    }
+
+    fn iter_prioritized_mut(&mut self) -> Box<dyn Iterator<Item = (&P, &mut Dyn)> + '_> {
+        let up_mut = self.trait_accessor.up_mut;
+        Box::new(self.iter_mut().map(move |(p, v)| (p, up_mut(v))))
+    }
+
+    fn iter_dyn(&self) -> Box<dyn Iterator<Item = (usize, &Dyn)> + '_> {
+        Box::new(OrderedStorage::<P, T, Dyn>::iter_dyn(self))
+    }
+
+    fn iter_dyn_mut(&mut self) -> Box<dyn Iterator<Item = (usize, &mut Dyn)> + '_> {
+        Box::new(OrderedStorage::<P, T, Dyn>::iter_dyn_mut(self))
+    }
+
+    fn indices(&self) -> Box<dyn Iterator<Item = usize> + '_> {
+        Box::new(OrderedStorage::<P, T, Dyn>::indices(self))
+    }
+
+    fn storage_type_name(&self) -> &'static str {
+        type_name::<Self>()
+    }
+
+    fn push_any(&mut self, value: Box<dyn Any>) -> Result<usize, Box<dyn Any>> {
+        Err(value)
+    }
+
+    fn add_insert_hook_dyn(&mut self, hook: Hook<Dyn>) {
+        self.hooks.insert_dyn.push(hook);
+    }
+
+    fn add_remove_hook_dyn(&mut self, hook: Hook<Dyn>) {
+        self.hooks.remove_dyn.push(hook);
+    }
+
+    fn as_storage_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_storage_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// Marker type for the ordered storage family, keeping values sorted by priorities of type `P`.
+///
+/// Priorities must be `Ord + Clone`. Since values need a priority, [`TraitTypeMap::insert_boxed`]
+/// always hands values back for this family.
+///
+/// # Examples
+///
+/// ```rust
+/// use trait_type_map::{impl_trait_accessible, AsAny, OrderedFamily, TraitTypeMap};
+///
+/// trait System: AsAny {}
+/// struct Physics;
+/// impl System for Physics {}
+/// impl_trait_accessible!(dyn System; Physics);
+///
+/// let mut map: TraitTypeMap<dyn System, OrderedFamily<i32>> = TraitTypeMap::new();
+/// map.register_type_storage::<Physics>();
+/// assert!(map.insert_boxed(Box::new(Physics)).is_err());
+/// assert_eq!(map.get_storage::<Physics>().len(), 0);
+/// ```
+pub struct OrderedFamily<P>(PhantomData<fn() -> P>);
+
+/* =============== Storage family binding ================= */
+
+/// Storage family trait that determines how values are stored. The family trait is generic over the **trait object** `Dyn`.
+/// Each impl chooses its trait type (`dyn TraitVecStorage<Dyn>`, `dyn TraitVecOptionStorage<Dyn>`, `dyn TraitOptionStorage<Dyn>`,
+/// `dyn TraitSparseSetStorage<Dyn>`, `dyn TraitKeyedStorage<Dyn, K>`, or `dyn TraitOrderedStorage<Dyn, P>`) and its typed
+/// storage (`VecStorage<T, Dyn>`, `VecOptionStorage<T, Dyn>`, `OptionStorage<T, Dyn>`, `SparseSetStorage<T, Dyn>`,
+/// `KeyedStorage<K, T, Dyn>`, or `OrderedStorage<P, T, Dyn>`).
+pub trait StorageFamily<Dyn: ?Sized + 'static> {
+    type Trait: ?Sized + 'static;
+    type Storage<T: 'static>: 'static;
+
+    fn make<T: 'static>(trait_accessor: TraitAccessor<T, Dyn>) -> Box<Self::Trait>;
+    fn try_storage_ref<T: 'static>(e: &Self::Trait)
+        -> Result<&Self::Storage<T>, TraitTypeMapError>;
+    fn try_storage_mut<T: 'static>(
+        e: &mut Self::Trait,
+    ) -> Result<&mut Self::Storage<T>, TraitTypeMapError>;
+    fn clone_storage<T: Clone + 'static>(e: &Self::Trait) -> Box<Self::Trait>;
+    fn iter_dyn(e: &Self::Trait) -> Box<dyn Iterator<Item = (usize, &Dyn)> + '_>;
+    fn iter_dyn_mut(e: &mut Self::Trait) -> Box<dyn Iterator<Item = (usize, &mut Dyn)> + '_>;
+    fn push_any(e: &mut Self::Trait, value: Box<dyn Any>) -> Result<usize, Box<dyn Any>>;
+    fn iter_indexed<T: 'static>(s: &Self::Storage<T>)
+        -> Box<dyn Iterator<Item = (usize, &T)> + '_>;
+    fn iter_indexed_mut<T: 'static>(
+        s: &mut Self::Storage<T>,
+    ) -> Box<dyn Iterator<Item = (usize, &mut T)> + '_>;
+
+    fn add_insert_hook<T: 'static>(s: &mut Self::Storage<T>, hook: Hook<T>);
+    fn add_remove_hook<T: 'static>(s: &mut Self::Storage<T>, hook: Hook<T>);
+    fn add_insert_hook_dyn(e: &mut Self::Trait, hook: Hook<Dyn>);
+    fn add_remove_hook_dyn(e: &mut Self::Trait, hook: Hook<Dyn>);
+
+    /// Whether [`push_any`](Self::push_any) can store values. `false` for families whose values need a key.
+    fn supports_push_any() -> bool {
+        true
+    }
+
+    /// Set the tick recorded for subsequent changes. A no-op for families without change tracking.
+    fn set_change_tick(_e: &mut Self::Trait, _tick: u64) {}
+
+    /// Drain the recorded changes of a storage. Empty for families without change tracking.
+    fn drain_changes(_e: &mut Self::Trait) -> Vec<(usize, ChangeFlags)> {
+        Vec::new()
+    }
+
+    /// Indices changed at or after `tick`. Empty for families without change tracking.
+    fn changed_since(_e: &Self::Trait, _tick: u64) -> Vec<usize> {
+        Vec::new()
+    }
+
+    fn storage_ref<T: 'static>(e: &Self::Trait) -> &Self::Storage<T> {
+        Self::try_storage_ref::<T>(e).unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    fn storage_mut<T: 'static>(e: &mut Self::Trait) -> &mut Self::Storage<T> {
+        Self::try_storage_mut::<T>(e).unwrap_or_else(|err| panic!("{err}"))
+    }
+}
+
+impl<D: ?Sized + 'static> StorageFamily<D> for VecFamily {
+    type Trait = dyn TraitVecStorage<D>;
+    type Storage<T: 'static> = VecStorage<T, D>;
+
+    fn make<T: 'static>(trait_accessor: TraitAccessor<T, D>) -> Box<Self::Trait> {
+        Box::new(VecStorage::<T, D>::new(trait_accessor))
+    }
+
+    fn try_storage_ref<T: 'static>(
+        e: &Self::Trait,
+    ) -> Result<&Self::Storage<T>, TraitTypeMapError> {
+        e.as_storage_any().downcast_ref::<VecStorage<T, D>>().ok_or(
+            TraitTypeMapError::WrongStorageType {
+                expected: type_name::<VecStorage<T, D>>(),
+                found: e.storage_type_name(),
+            },
+        )
+    }
+
+    fn try_storage_mut<T: 'static>(
+        e: &mut Self::Trait,
+    ) -> Result<&mut Self::Storage<T>, TraitTypeMapError> {
+        let found = e.storage_type_name();
+        e.as_storage_any_mut()
+            .downcast_mut::<VecStorage<T, D>>()
+            .ok_or(TraitTypeMapError::WrongStorageType {
+                expected: type_name::<VecStorage<T, D>>(),
+                found,
+            })
+    }
+
+    fn clone_storage<T: Clone + 'static>(e: &Self::Trait) -> Box<Self::Trait> {
+        Box::new(Self::storage_ref::<T>(e).clone())
+    }
+
+    fn iter_dyn(e: &Self::Trait) -> Box<dyn Iterator<Item = (usize, &D)> + '_> {
+        e.iter_dyn()
+    }
+
+    fn iter_dyn_mut(e: &mut Self::Trait) -> Box<dyn Iterator<Item = (usize, &mut D)> + '_> {
+        e.iter_dyn_mut()
+    }
+
+    fn push_any(e: &mut Self::Trait, value: Box<dyn Any>) -> Result<usize, Box<dyn Any>> {
+        e.push_any(value)
+    }
+
+    fn iter_indexed<T: 'static>(
+        s: &Self::Storage<T>,
+    ) -> Box<dyn Iterator<Item = (usize, &T)> + '_> {
+        Box::new(s.iter_indexed())
+    }
+
+    fn iter_indexed_mut<T: 'static>(
+        s: &mut Self::Storage<T>,
+    ) -> Box<dyn Iterator<Item = (usize, &mut T)> + '_> {
+        Box::new(s.iter_indexed_mut())
+    }
+
+    fn add_insert_hook<T: 'static>(s: &mut Self::Storage<T>, hook: Hook<T>) {
+        s.hooks.insert.push(hook);
+    }
+
+    fn add_remove_hook<T: 'static>(s: &mut Self::Storage<T>, hook: Hook<T>) {
+        s.hooks.remove.push(hook);
+    }
+
+    fn add_insert_hook_dyn(e: &mut Self::Trait, hook: Hook<D>) {
+        e.add_insert_hook_dyn(hook);
+    }
+
+    fn add_remove_hook_dyn(e: &mut Self::Trait, hook: Hook<D>) {
+        e.add_remove_hook_dyn(hook);
+    }
+}
+
+impl<D: ?Sized + 'static> StorageFamily<D> for VecOptionFamily {
+    type Trait = dyn TraitVecOptionStorage<D>;
+    type Storage<T: 'static> = VecOptionStorage<T, D>;
+
+    fn make<T: 'static>(trait_accessor: TraitAccessor<T, D>) -> Box<Self::Trait> {
+        Box::new(VecOptionStorage::<T, D>::new(trait_accessor))
+    }
+
+    fn try_storage_ref<T: 'static>(
+        e: &Self::Trait,
+    ) -> Result<&Self::Storage<T>, TraitTypeMapError> {
+        e.as_storage_any()
+            .downcast_ref::<VecOptionStorage<T, D>>()
+            .ok_or(TraitTypeMapError::WrongStorageType {
+                expected: type_name::<VecOptionStorage<T, D>>(),
+                found: e.storage_type_name(),
+            })
+    }
+
+    fn try_storage_mut<T: 'static>(
+        e: &mut Self::Trait,
+    ) -> Result<&mut Self::Storage<T>, TraitTypeMapError> {
+        let found = e.storage_type_name();
+        e.as_storage_any_mut()
+            .downcast_mut::<VecOptionStorage<T, D>>()
+            .ok_or(TraitTypeMapError::WrongStorageType {
+                expected: type_name::<VecOptionStorage<T, D>>(),
+                found,
+            })
+    }
+
+    fn clone_storage<T: Clone + 'static>(e: &Self::Trait) -> Box<Self::Trait> {
+        Box::new(Self::storage_ref::<T>(e).clone())
+    }
+
+    fn iter_dyn(e: &Self::Trait) -> Box<dyn Iterator<Item = (usize, &D)> + '_> {
+        e.iter_dyn()
+    }
+
+    fn iter_dyn_mut(e: &mut Self::Trait) -> Box<dyn Iterator<Item = (usize, &mut D)> + '_> {
+        e.iter_dyn_mut()
+    }
+
+    fn push_any(e: &mut Self::Trait, value: Box<dyn Any>) -> Result<usize, Box<dyn Any>> {
+        e.push_any(value)
+    }
+
+    fn iter_indexed<T: 'static>(
+        s: &Self::Storage<T>,
+    ) -> Box<dyn Iterator<Item = (usize, &T)> + '_> {
+        Box::new(s.iter_indexed())
+    }
+
+    fn iter_indexed_mut<T: 'static>(
+        s: &mut Self::Storage<T>,
+    ) -> Box<dyn Iterator<Item = (usize, &mut T)> + '_> {
+        Box::new(s.iter_indexed_mut())
+    }
+
+    fn set_change_tick(e: &mut Self::Trait, tick: u64) {
+        e.set_change_tick(tick);
+    }
+
+    fn drain_changes(e: &mut Self::Trait) -> Vec<(usize, ChangeFlags)> {
+        e.drain_changes()
+    }
+
+    fn changed_since(e: &Self::Trait, tick: u64) -> Vec<usize> {
+        e.changed_since(tick)
+    }
+
+    fn add_insert_hook<T: 'static>(s: &mut Self::Storage<T>, hook: Hook<T>) {
+        s.hooks.insert.push(hook);
+    }
+
+    fn add_remove_hook<T: 'static>(s: &mut Self::Storage<T>, hook: Hook<T>) {
+        s.hooks.remove.push(hook);
+    }
+
+    fn add_insert_hook_dyn(e: &mut Self::Trait, hook: Hook<D>) {
+        e.add_insert_hook_dyn(hook);
+    }
+
+    fn add_remove_hook_dyn(e: &mut Self::Trait, hook: Hook<D>) {
+        e.add_remove_hook_dyn(hook);
+    }
+}
+
+impl<D: ?Sized + 'static> StorageFamily<D> for OptionFamily {
+    type Trait = dyn TraitOptionStorage<D>;
+    type Storage<T: 'static> = OptionStorage<T, D>;
+
+    fn make<T: 'static>(trait_accessor: TraitAccessor<T, D>) -> Box<Self::Trait> {
+        Box::new(OptionStorage::<T, D>::new(trait_accessor))
+    }
+
+    fn try_storage_ref<T: 'static>(
+        e: &Self::Trait,
+    ) -> Result<&Self::Storage<T>, TraitTypeMapError> {
+        e.as_storage_any()
+            .downcast_ref::<OptionStorage<T, D>>()
+            .ok_or(TraitTypeMapError::WrongStorageType {
+                expected: type_name::<OptionStorage<T, D>>(),
+                found: e.storage_type_name(),
+            })
+    }
+
+    fn try_storage_mut<T: 'static>(
+        e: &mut Self::Trait,
+    ) -> Result<&mut Self::Storage<T>, TraitTypeMapError> {
+        let found = e.storage_type_name();
+        e.as_storage_any_mut()
+            .downcast_mut::<OptionStorage<T, D>>()
+            .ok_or(TraitTypeMapError::WrongStorageType {
+                expected: type_name::<OptionStorage<T, D>>(),
+                found,
+            })
+    }
+
+    fn clone_storage<T: Clone + 'static>(e: &Self::Trait) -> Box<Self::Trait> {
+        Box::new(Self::storage_ref::<T>(e).clone())
+    }
+
+    fn iter_dyn(e: &Self::Trait) -> Box<dyn Iterator<Item = (usize, &D)> + '_> {
+        e.iter_dyn()
+    }
+
+    fn iter_dyn_mut(e: &mut Self::Trait) -> Box<dyn Iterator<Item = (usize, &mut D)> + '_> {
+        e.iter_dyn_mut()
+    }
+
+    fn push_any(e: &mut Self::Trait, value: Box<dyn Any>) -> Result<usize, Box<dyn Any>> {
+        e.push_any(value)
+    }
+
+    fn iter_indexed<T: 'static>(
+        s: &Self::Storage<T>,
+    ) -> Box<dyn Iterator<Item = (usize, &T)> + '_> {
+        Box::new(s.data.iter().map(|v| (0, v)))
+    }
+
+    fn iter_indexed_mut<T: 'static>(
+        s: &mut Self::Storage<T>,
+    ) -> Box<dyn Iterator<Item = (usize, &mut T)> + '_> {
+        Box::new(s.get_mut().into_iter().map(|v| (0, v)))
+    }
+
+    fn set_change_tick(e: &mut Self::Trait, tick: u64) {
+        e.set_change_tick(tick);
+    }
+
+    fn drain_changes(e: &mut Self::Trait) -> Vec<(usize, ChangeFlags)> {
+        e.drain_changes()
+    }
+
+    fn changed_since(e: &Self::Trait, tick: u64) -> Vec<usize> {
+        e.changed_since(tick)
+    }
+
+    fn add_insert_hook<T: 'static>(s: &mut Self::Storage<T>, hook: Hook<T>) {
+        s.hooks.insert.push(hook);
+    }
+
+    fn add_remove_hook<T: 'static>(s: &mut Self::Storage<T>, hook: Hook<T>) {
+        s.hooks.remove.push(hook);
+    }
+
+    fn add_insert_hook_dyn(e: &mut Self::Trait, hook: Hook<D>) {
+        e.add_insert_hook_dyn(hook);
+    }
+
+    fn add_remove_hook_dyn(e: &mut Self::Trait, hook: Hook<D>) {
+        e.add_remove_hook_dyn(hook);
+    }
+}
+
+impl<D: ?Sized + 'static> StorageFamily<D> for SparseSetFamily {
+    type Trait = dyn TraitSparseSetStorage<D>;
+    type Storage<T: 'static> = SparseSetStorage<T, D>;
+
+    fn make<T: 'static>(trait_accessor: TraitAccessor<T, D>) -> Box<Self::Trait> {
+        Box::new(SparseSetStorage::<T, D>::new(trait_accessor))
+    }
+
+    fn try_storage_ref<T: 'static>(
+        e: &Self::Trait,
+    ) -> Result<&Self::Storage<T>, TraitTypeMapError> {
+        e.as_storage_any()
+            .downcast_ref::<SparseSetStorage<T, D>>()
+            .ok_or(TraitTypeMapError::WrongStorageType {
+                expected: type_name::<SparseSetStorage<T, D>>(),
+                found: e.storage_type_name(),
+            })
+    }
+
+    fn try_storage_mut<T: 'static>(
+        e: &mut Self::Trait,
+    ) -> Result<&mut Self::Storage<T>, TraitTypeMapError> {
+        let found = e.storage_type_name();
+        e.as_storage_any_mut()
+            .downcast_mut::<SparseSetStorage<T, D>>()
+            .ok_or(TraitTypeMapError::WrongStorageType {
+                expected: type_name::<SparseSetStorage<T, D>>(),
+                found,
+            })
+    }
+
+    fn clone_storage<T: Clone + 'static>(e: &Self::Trait) -> Box<Self::Trait> {
+        Box::new(Self::storage_ref::<T>(e).clone())
+    }
+
+    fn iter_dyn(e: &Self::Trait) -> Box<dyn Iterator<Item = (usize, &D)> + '_> {
+        e.iter_dyn()
+    }
+
+    fn iter_dyn_mut(e: &mut Self::Trait) -> Box<dyn Iterator<Item = (usize, &mut D)> + '_> {
+        e.iter_dyn_mut()
+    }
+
+    fn push_any(e: &mut Self::Trait, value: Box<dyn Any>) -> Result<usize, Box<dyn Any>> {
+        e.push_any(value)
+    }
+
+    fn iter_indexed<T: 'static>(
+        s: &Self::Storage<T>,
+    ) -> Box<dyn Iterator<Item = (usize, &T)> + '_> {
+        Box::new(s.iter_indexed())
+    }
+
+    fn iter_indexed_mut<T: 'static>(
+        s: &mut Self::Storage<T>,
+    ) -> Box<dyn Iterator<Item = (usize, &mut T)> + '_> {
+        Box::new(s.iter_indexed_mut())
+    }
+
+    fn set_change_tick(e: &mut Self::Trait, tick: u64) {
+        e.set_change_tick(tick);
+    }
+
+    fn drain_changes(e: &mut Self::Trait) -> Vec<(usize, ChangeFlags)> {
+        e.drain_changes()
+    }
+
+    fn changed_since(e: &Self::Trait, tick: u64) -> Vec<usize> {
+        e.changed_since(tick)
+    }
+
+    fn add_insert_hook<T: 'static>(s: &mut Self::Storage<T>, hook: Hook<T>) {
+        s.hooks.insert.push(hook);
+    }
+
+    fn add_remove_hook<T: 'static>(s: &mut Self::Storage<T>, hook: Hook<T>) {
+        s.hooks.remove.push(hook);
+    }
+
+    fn add_insert_hook_dyn(e: &mut Self::Trait, hook: Hook<D>) {
+        e.add_insert_hook_dyn(hook);
+    }
+
+    fn add_remove_hook_dyn(e: &mut Self::Trait, hook: Hook<D>) {
+        e.add_remove_hook_dyn(hook);
+    }
+}
+
+impl<D: ?Sized + 'static, K: Hash + Eq + Clone + 'static> StorageFamily<D> for HashMapFamily<K> {
+    type Trait = dyn TraitKeyedStorage<D, K>;
+    type Storage<T: 'static> = KeyedStorage<K, T, D>;
+
+    fn make<T: 'static>(trait_accessor: TraitAccessor<T, D>) -> Box<Self::Trait> {
+        Box::new(KeyedStorage::<K, T, D>::new(trait_accessor))
+    }
+
+    fn try_storage_ref<T: 'static>(
+        e: &Self::Trait,
+    ) -> Result<&Self::Storage<T>, TraitTypeMapError> {
+        e.as_storage_any()
+            .downcast_ref::<KeyedStorage<K, T, D>>()
+            .ok_or(TraitTypeMapError::WrongStorageType {
+                expected: type_name::<KeyedStorage<K, T, D>>(),
+                found: e.storage_type_name(),
+            })
+    }
+
+    fn try_storage_mut<T: 'static>(
+        e: &mut Self::Trait,
+    ) -> Result<&mut Self::Storage<T>, TraitTypeMapError> {
+        let found = e.storage_type_name();
+        e.as_storage_any_mut()
+            .downcast_mut::<KeyedStorage<K, T, D>>()
+            .ok_or(TraitTypeMapError::WrongStorageType {
+                expected: type_name::<KeyedStorage<K, T, D>>(),
+                found,
+            })
+    }
+
+    fn clone_storage<T: Clone + 'static>(e: &Self::Trait) -> Box<Self::Trait> {
+        Box::new(Self::storage_ref::<T>(e).clone())
+    }
+
+    fn iter_dyn(e: &Self::Trait) -> Box<dyn Iterator<Item = (usize, &D)> + '_> {
+        e.iter_dyn()
+    }
+
+    fn iter_dyn_mut(e: &mut Self::Trait) -> Box<dyn Iterator<Item = (usize, &mut D)> + '_> {
+        e.iter_dyn_mut()
+    }
+
+    fn push_any(e: &mut Self::Trait, value: Box<dyn Any>) -> Result<usize, Box<dyn Any>> {
+        e.push_any(value)
+    }
+
+    fn iter_indexed<T: 'static>(
+        s: &Self::Storage<T>,
+    ) -> Box<dyn Iterator<Item = (usize, &T)> + '_> {
+        Box::new(s.iter_indexed())
+    }
+
+    fn iter_indexed_mut<T: 'static>(
+        s: &mut Self::Storage<T>,
+    ) -> Box<dyn Iterator<Item = (usize, &mut T)> + '_> {
+        Box::new(s.iter_indexed_mut())
+    }
+
+    fn add_insert_hook<T: 'static>(s: &mut Self::Storage<T>, hook: Hook<T>) {
+        s.hooks.insert.push(hook);
+    }
+
+    fn add_remove_hook<T: 'static>(s: &mut Self::Storage<T>, hook: Hook<T>) {
+        s.hooks.remove.push(hook);
+    }
+
+    fn add_insert_hook_dyn(e: &mut Self::Trait, hook: Hook<D>) {
+        e.add_insert_hook_dyn(hook);
+    }
+
+    fn add_remove_hook_dyn(e: &mut Self::Trait, hook: Hook<D>) {
+        e.add_remove_hook_dyn(hook);
+    }
+
+    fn supports_push_any() -> bool {
+        false
+    }
+}
+
+impl<D: ?Sized + 'static, P: Ord + Clone + 'static> StorageFamily<D> for OrderedFamily<P> {
+    type Trait = dyn TraitOrderedStorage<D, P>;
+    type Storage<T: 'static> = OrderedStorage<P, T, D>;
+
+    fn make<T: 'static>(trait_accessor: TraitAccessor<T, D>) -> Box<Self::Trait> {
+        Box::new(OrderedStorage::<P, T, D>::new(trait_accessor))
+    }
+
+    fn try_storage_ref<T: 'static>(
+        e: &Self::Trait,
+    ) -> Result<&Self::Storage<T>, TraitTypeMapError> {
+        e.as_storage_any()
+            .downcast_ref::<OrderedStorage<P, T, D>>()
+            .ok_or(TraitTypeMapError::WrongStorageType {
+                expected: type_name::<OrderedStorage<P, T, D>>(),
+                found: e.storage_type_name(),
+            })
+    }
+
+    fn try_storage_mut<T: 'static>(
+        e: &mut Self::Trait,
+    ) -> Result<&mut Self::Storage<T>, TraitTypeMapError> {
+        let found = e.storage_type_name();
+        e.as_storage_any_mut()
+            .downcast_mut::<OrderedStorage<P, T, D>>()
+            .ok_or(TraitTypeMapError::WrongStorageType {
+                expected: type_name::<OrderedStorage<P, T, D>>(),
+                found,
+            })
+    }
+
+    fn clone_storage<T: Clone + 'static>(e: &Self::Trait) -> Box<Self::Trait> {
+        Box::new(Self::storage_ref::<T>(e).clone())
+    }
+
+    fn iter_dyn(e: &Self::Trait) -> Box<dyn Iterator<Item = (usize, &D)> + '_> {
+        e.iter_dyn()
+    }
+
+    fn iter_dyn_mut(e: &mut Self::Trait) -> Box<dyn Iterator<Item = (usize, &mut D)> + '_> {
+        e.iter_dyn_mut()
+    }
+
+    fn push_any(e: &mut Self::Trait, value: Box<dyn Any>) -> Result<usize, Box<dyn Any>> {
+        e.push_any(value)
+    }
+
+    fn iter_indexed<T: 'static>(
+        s: &Self::Storage<T>,
+    ) -> Box<dyn Iterator<Item = (usize, &T)> + '_> {
+        Box::new(s.iter_indexed())
+    }
+
+    fn iter_indexed_mut<T: 'static>(
+        s: &mut Self::Storage<T>,
+    ) -> Box<dyn Iterator<Item = (usize, &mut T)> + '_> {
+        Box::new(s.iter_indexed_mut())
+    }
+
+    fn add_insert_hook<T: 'static>(s: &mut Self::Storage<T>, hook: Hook<T>) {
+        s.hooks.insert.push(hook);
+    }
+
+    fn add_remove_hook<T: 'static>(s: &mut Self::Storage<T>, hook: Hook<T>) {
+        s.hooks.remove.push(hook);
+    }
+
+    fn add_insert_hook_dyn(e: &mut Self::Trait, hook: Hook<D>) {
+        e.add_insert_hook_dyn(hook);
+    }
+
+    fn add_remove_hook_dyn(e: &mut Self::Trait, hook: Hook<D>) {
+        e.add_remove_hook_dyn(hook);
+    }
+
+    fn supports_push_any() -> bool {
+        false
+    }
+}
+
+/// Storage family whose storages can be shared between threads.
+///
+/// `SyncTrait` is the family's `Trait` object with `Send + Sync` bounds added. A storage is
+/// `Send + Sync` whenever the stored type is, so only such types can be stored behind it.
+pub trait SyncStorageFamily<Dyn: ?Sized + 'static>: StorageFamily<Dyn> {
+    type SyncTrait: ?Sized + Send + Sync + 'static;
+
+    fn make_sync<T: Send + Sync + 'static>(
+        trait_accessor: TraitAccessor<T, Dyn>,
+    ) -> Box<Self::SyncTrait>;
+    fn as_trait(e: &Self::SyncTrait) -> &Self::Trait;
+    fn as_trait_mut(e: &mut Self::SyncTrait) -> &mut Self::Trait;
+}
+
+impl<D: ?Sized + 'static> SyncStorageFamily<D> for VecFamily {
+    type SyncTrait = dyn TraitVecStorage<D> + Send + Sync;
+
+    fn make_sync<T: Send + Sync + 'static>(
+        trait_accessor: TraitAccessor<T, D>,
+    ) -> Box<Self::SyncTrait> {
+        Box::new(VecStorage::<T, D>::new(trait_accessor))
+    }
+
+    fn as_trait(e: &Self::SyncTrait) -> &Self::Trait {
+        e
+    }
+
+    fn as_trait_mut(e: &mut Self::SyncTrait) -> &mut Self::Trait {
+        e
+    }
+}
+
+impl<D: ?Sized + 'static> SyncStorageFamily<D> for VecOptionFamily {
+    type SyncTrait = dyn TraitVecOptionStorage<D> + Send + Sync;
+
+    fn make_sync<T: Send + Sync + 'static>(
+        trait_accessor: TraitAccessor<T, D>,
+    ) -> Box<Self::SyncTrait> {
+        Box::new(VecOptionStorage::<T, D>::new(trait_accessor))
+    }
+
+    fn as_trait(e: &Self::SyncTrait) -> &Self::Trait {
+        e
+    }
+
+    fn as_trait_mut(e: &mut Self::SyncTrait) -> &mut Self::Trait {
+        e
+    }
+}
+
+impl<D: ?Sized + 'static> SyncStorageFamily<D> for OptionFamily {
+    type SyncTrait = dyn TraitOptionStorage<D> + Send + Sync;
+
+    fn make_sync<T: Send + Sync + 'static>(
+        trait_accessor: TraitAccessor<T, D>,
+    ) -> Box<Self::SyncTrait> {
+        Box::new(OptionStorage::<T, D>::new(trait_accessor))
+    }
+
+    fn as_trait(e: &Self::SyncTrait) -> &Self::Trait {
+        e
+    }
+
+    fn as_trait_mut(e: &mut Self::SyncTrait) -> &mut Self::Trait {
+        e
+    }
+}
+
+impl<D: ?Sized + 'static> SyncStorageFamily<D> for SparseSetFamily {
+    type SyncTrait = dyn TraitSparseSetStorage<D> + Send + Sync;
+
+    fn make_sync<T: Send + Sync + 'static>(
+        trait_accessor: TraitAccessor<T, D>,
+    ) -> Box<Self::SyncTrait> {
+        Box::new(SparseSetStorage::<T, D>::new(trait_accessor))
+    }
+
+    fn as_trait(e: &Self::SyncTrait) -> &Self::Trait {
+        e
+    }
+
+    fn as_trait_mut(e: &mut Self::SyncTrait) -> &mut Self::Trait {
+        e
+    }
+}
+
+impl<D: ?Sized + 'static, K: Hash + Eq + Clone + Send + Sync + 'static> SyncStorageFamily<D>
+    for HashMapFamily<K>
+{
+    type SyncTrait = dyn TraitKeyedStorage<D, K> + Send + Sync;
+
+    fn make_sync<T: Send + Sync + 'static>(
+        trait_accessor: TraitAccessor<T, D>,
+    ) -> Box<Self::SyncTrait> {
+        Box::new(KeyedStorage::<K, T, D>::new(trait_accessor))
+    }
+
+    fn as_trait(e: &Self::SyncTrait) -> &Self::Trait {
+        e
+    }
+
+    fn as_trait_mut(e: &mut Self::SyncTrait) -> &mut Self::Trait {
+        e
+    }
+}
+
+impl<D: ?Sized + 'static, P: Ord + Clone + Send + Sync + 'static> SyncStorageFamily<D>
+    for OrderedFamily<P>
+{
+    type SyncTrait = dyn TraitOrderedStorage<D, P> + Send + Sync;
+
+    fn make_sync<T: Send + Sync + 'static>(
+        trait_accessor: TraitAccessor<T, D>,
+    ) -> Box<Self::SyncTrait> {
+        Box::new(OrderedStorage::<P, T, D>::new(trait_accessor))
+    }
+
+    fn as_trait(e: &Self::SyncTrait) -> &Self::Trait {
+        e
+    }
+
+    fn as_trait_mut(e: &mut Self::SyncTrait) -> &mut Self::Trait {
+        e
+    }
+}
+
+/* ====================== Downcasting ====================== */
+
+/// Bridge from a trait object back to [`Any`], enabling downcasts to the concrete type.
+///
+/// Implemented for every `'static` type. Add it as a supertrait (`trait Animal: AsAny`)
+/// to make `dyn Animal` downcastable and usable with [`TraitTypeMap::insert_boxed`].
+pub trait AsAny: Any {
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
+    /// Name of the concrete type behind the trait object.
+    fn concrete_type_name(&self) -> &'static str;
+}
+
+impl<T: Any> AsAny for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+
+    fn concrete_type_name(&self) -> &'static str {
+        type_name::<T>()
+    }
+}
+
+/// Downcast a trait object reference to the concrete type `T`.
+///
+/// # Examples
+///
+/// ```rust
+/// use trait_type_map::{downcast_box, downcast_ref, AsAny};
+///
+/// trait Animal: AsAny {}
+/// struct Dog(u8);
+/// struct Cat;
+/// impl Animal for Dog {}
+/// impl Animal for Cat {}
+///
+/// let dog: Box<dyn Animal> = Box::new(Dog(3));
+/// assert_eq!(downcast_ref::<Dog, _>(&*dog).map(|d| d.0), Some(3));
+/// assert!(downcast_ref::<Cat, _>(&*dog).is_none());
+///
+/// let Err(dog) = downcast_box::<Cat, _>(dog) else { unreachable!() };
+/// assert_eq!(downcast_box::<Dog, _>(dog).ok().map(|d| d.0), Some(3));
+/// ```
+#[inline(always)]
+pub fn downcast_ref<T: 'static, Dyn: ?Sized + AsAny>(value: &Dyn) -> Option<&T> {
+    value.as_any().downcast_ref::<T>()
+}
+
+/// Mutable counterpart of [`downcast_ref`].
+#[inline(always)]
+pub fn downcast_mut<T: 'static, Dyn: ?Sized + AsAny>(value: &mut Dyn) -> Option<&mut T> {
+    value.as_any_mut().downcast_mut::<T>()
+}
+
+/// Downcast a boxed trait object to the concrete type `T`, handing it back unchanged on mismatch.
+pub fn downcast_box<T: 'static, Dyn: ?Sized + AsAny>(value: Box<Dyn>) -> Result<Box<T>, Box<Dyn>> {
+    // Deref first, as `Box<Dyn>` is itself `AsAny`
+    if (*value).as_any().is::<T>() {
+        Ok(value.into_any().downcast::<T>().unwrap())
+    } else {
+        Err(value)
+    }
+}
+
+/* ===================== One map type ====================== */
+
+/// Trait for types that can be accessed via a trait object.
+///
+/// Types implementing this trait can be stored in a `TraitTypeMap`.
+/// Use the `impl_trait_accessible!` macro to implement this trait, or `#[trait_accessible(...)]`
+/// with the `derive` feature enabled.
+pub trait TraitAccessible<Dyn: ?Sized> {
+    fn get_accessor() -> TraitAccessor<Self, Dyn>
+    where
+        Self: Sized;
+}
+
+/// A type-indexed map for storing values implementing a specific trait.
+///
+/// # Type Parameters
+///
+/// - `Dyn`: The trait object type (e.g., `dyn MyTrait`)
+/// - `F`: The storage family (`VecFamily`, `VecOptionFamily`, or `OptionFamily`)
+///
+/// # Examples
+///
+/// ```rust
+/// use trait_type_map::{impl_trait_accessible, TraitTypeMap, OptionFamily};
+///
+/// trait Animal {
+///     fn name(&self) -> &str;
+/// }
+///
+/// struct Dog { name: String }
+/// impl Animal for Dog {
+///     fn name(&self) -> &str { &self.name }
+/// }
+///
+/// impl_trait_accessible!(dyn Animal; Dog);
+///
+/// let mut map: TraitTypeMap<dyn Animal, OptionFamily> = TraitTypeMap::new();
+/// map.register_type_storage::<Dog>();
+/// map.get_storage_mut::<Dog>().set(Dog { name: "Rex".into() });
+///
+/// if let Some(dog) = map.get_storage::<Dog>().get() {
+///     assert_eq!(dog.name(), "Rex");
+/// }
+/// ```
+pub struct TraitTypeMap<Dyn: ?Sized + 'static, F: StorageFamily<Dyn>, S = DefaultHashBuilder> {
+    /// Registered storages, in registration order.
+    entries: IndexMap<TypeId, RegisteredStorage<F::Trait>, S>,
+    /// View accessors, keyed by view trait object and then by stored type.
+    views: HashMap<TypeId, HashMap<TypeId, Arc<dyn Any + Send + Sync>>>,
+    change_tick: u64,
+    dyn_hooks: DynHooks<Dyn>,
+}
+
+/// Trait object hooks of a map, attached to every storage including ones registered later.
+struct DynHooks<Dyn: ?Sized> {
+    insert: Vec<Hook<Dyn>>,
+    remove: Vec<Hook<Dyn>>,
+}
+
+impl<Dyn: ?Sized + 'static> DynHooks<Dyn> {
+    fn new() -> Self {
+        Self {
+            insert: Vec::new(),
+            remove: Vec::new(),
+        }
+    }
+
+    fn attach<F: StorageFamily<Dyn>>(&self, storage: &mut F::Trait) {
+        for hook in &self.insert {
+            F::add_insert_hook_dyn(storage, hook.clone());
+        }
+        for hook in &self.remove {
+            F::add_remove_hook_dyn(storage, hook.clone());
+        }
+    }
+}
+
+/// Clones a type-erased storage; captures the concrete `T: Clone` at registration time.
+pub(crate) type CloneStorageFn<S> = fn(&S) -> Box<S>;
+
+/// A registered storage along with the name of the type it stores.
+struct RegisteredStorage<S: ?Sized> {
+    type_name: &'static str,
+    storage: Box<S>,
+    /// Set for types registered as cloneable
+    clone_storage: Option<CloneStorageFn<S>>,
+}
+
+impl<S: ?Sized> RegisteredStorage<S> {
+    fn new<T: 'static>(storage: Box<S>) -> Self {
+        Self {
+            type_name: type_name::<T>(),
+            storage,
+            clone_storage: None,
+        }
+    }
+
+    fn try_clone(&self) -> Result<Self, TraitTypeMapError> {
+        let clone_storage = self.clone_storage.ok_or(TraitTypeMapError::NotCloneable {
+            type_name: self.type_name,
+        })?;
+        Ok(Self {
+            type_name: self.type_name,
+            storage: clone_storage(&self.storage),
+            clone_storage: self.clone_storage,
+        })
+    }
+}
+
+impl<Dyn: ?Sized + 'static, F: StorageFamily<Dyn>, S: BuildHasher> fmt::Debug
+    for TraitTypeMap<Dyn, F, S>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TraitTypeMap")
+            .field(
+                "types",
+                &self
+                    .registered_types()
+                    .map(|(_, name)| name)
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+impl<Dyn: ?Sized + 'static, F: StorageFamily<Dyn>, S: BuildHasher + Default> Default
+    for TraitTypeMap<Dyn, F, S>
+{
+    fn default() -> Self {
+        Self::with_hasher(S::default())
+    }
+}
+
+impl<Dyn: ?Sized + 'static, F: StorageFamily<Dyn>> TraitTypeMap<Dyn, F> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new map with pre-allocated capacity for the given number of types.
+    /// This can improve performance when you know how many types you'll store.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_and_hasher(capacity, DefaultHashBuilder::default())
+    }
+}
+
+impl<Dyn: ?Sized + 'static, F: StorageFamily<Dyn>, S: BuildHasher> TraitTypeMap<Dyn, F, S> {
+    /// Create a new map that hashes `TypeId`s with `hash_builder`, e.g. [`TypeIdBuildHasher`](crate::TypeIdBuildHasher).
+    pub fn with_hasher(hash_builder: S) -> Self {
+        Self::with_capacity_and_hasher(0, hash_builder)
+    }
+
+    /// Create a new map with pre-allocated capacity that hashes `TypeId`s with `hash_builder`.
+    pub fn with_capacity_and_hasher(capacity: usize, hash_builder: S) -> Self {
+        Self {
+            entries: IndexMap::with_capacity_and_hasher(capacity, hash_builder),
+            views: HashMap::default(),
+            change_tick: 1,
+            dyn_hooks: DynHooks::new(),
+        }
+    }
+
+    /// Register a storage for `T`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `T` is already registered. See [`try_register_type_storage`](Self::try_register_type_storage).
+    pub fn register_type_storage<T>(&mut self)
+    where
+        T: 'static + TraitAccessible<Dyn>,
+    {
+        if let Err(err) = self.try_register_type_storage::<T>() {
+            panic!("{err}");
+        }
+    }
+
+    /// Register a storage for `T`, failing with [`TraitTypeMapError::AlreadyRegistered`]
+    /// instead of panicking if it already exists.
+    pub fn try_register_type_storage<T>(&mut self) -> Result<(), TraitTypeMapError>
+    where
+        T: 'static + TraitAccessible<Dyn>,
+    {
+        if self.insert_storage::<T>(F::make::<T>(T::get_accessor()), None) {
+            Ok(())
+        } else {
+            Err(TraitTypeMapError::AlreadyRegistered {
+                type_name: type_name::<T>(),
+            })
+        }
+    }
+
+    /// Get the storage entry for `T`, for in-place registration on first use.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use trait_type_map::{impl_trait_accessible, TraitTypeMap, VecOptionFamily};
+    ///
+    /// trait Plugin {}
+    /// struct Physics;
+    /// impl Plugin for Physics {}
+    /// impl_trait_accessible!(dyn Plugin; Physics);
+    ///
+    /// let mut map: TraitTypeMap<dyn Plugin, VecOptionFamily> = TraitTypeMap::new();
+    ///
+    /// // No up-front `register_type_storage` needed
+    /// map.entry::<Physics>().or_register().push(Physics);
+    /// map.entry::<Physics>().or_register().push(Physics);
+    /// assert_eq!(map.get_storage::<Physics>().iter().count(), 2);
+    /// ```
+    pub fn entry<T>(&mut self) -> StorageEntry<'_, Dyn, F, T, S>
+    where
+        T: 'static,
+    {
+        StorageEntry {
+            map: self,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Get mutable access to the storage for `T`, registering it first if needed.
+    ///
+    /// Shorthand for `entry::<T>().or_register()`.
+    #[inline]
+    pub fn storage_mut_or_register<T>(&mut self) -> &mut F::Storage<T>
+    where
+        T: 'static + TraitAccessible<Dyn>,
+    {
+        self.entry::<T>().or_register()
+    }
+
+    /// Register a storage for `T` that can be cloned along with the map.
+    ///
+    /// A map can only be cloned with [`try_clone`](Self::try_clone) if every registered type was registered this way.
+    /// [`CloneableTraitTypeMap`](crate::CloneableTraitTypeMap) enforces this at compile time and implements `Clone`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `T` is already registered. See [`try_register_cloneable_type_storage`](Self::try_register_cloneable_type_storage).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use trait_type_map::{impl_trait_accessible, TraitTypeMap, VecOptionFamily};
+    ///
+    /// trait Plugin {}
+    /// #[derive(Clone)]
+    /// struct Counter(u32);
+    /// impl Plugin for Counter {}
+    /// impl_trait_accessible!(dyn Plugin; Counter);
+    ///
+    /// let mut map: TraitTypeMap<dyn Plugin, VecOptionFamily> = TraitTypeMap::new();
+    /// map.register_cloneable_type_storage::<Counter>();
+    /// let idx = map.get_storage_mut::<Counter>().push(Counter(1));
+    ///
+    /// let snapshot = map.try_clone().unwrap();
+    /// map.get_storage_mut::<Counter>().get_mut(idx).unwrap().0 = 2;
+    ///
+    /// assert_eq!(snapshot.get_storage::<Counter>().get(idx).unwrap().0, 1);
+    /// ```
+    pub fn register_cloneable_type_storage<T>(&mut self)
+    where
+        T: 'static + Clone + TraitAccessible<Dyn>,
+    {
+        if let Err(err) = self.try_register_cloneable_type_storage::<T>() {
+            panic!("{err}");
+        }
+    }
+
+    /// Register a cloneable storage for `T`, failing with [`TraitTypeMapError::AlreadyRegistered`]
+    /// instead of panicking if it already exists.
+    pub fn try_register_cloneable_type_storage<T>(&mut self) -> Result<(), TraitTypeMapError>
+    where
+        T: 'static + Clone + TraitAccessible<Dyn>,
+    {
+        let storage = F::make::<T>(T::get_accessor());
+        if self.insert_storage::<T>(storage, Some(F::clone_storage::<T>)) {
+            Ok(())
+        } else {
+            Err(TraitTypeMapError::AlreadyRegistered {
+                type_name: type_name::<T>(),
+            })
+        }
+    }
+
+    /// Insert an already constructed storage for `T`, returning `false` if `T` was registered.
+    pub(crate) fn insert_storage<T: 'static>(
+        &mut self,
+        mut storage: Box<F::Trait>,
+        clone_storage: Option<CloneStorageFn<F::Trait>>,
+    ) -> bool {
+        match self.entries.entry(TypeId::of::<T>()) {
+            Entry::Occupied(_) => false,
+            Entry::Vacant(slot) => {
+                F::set_change_tick(&mut *storage, self.change_tick);
+                self.dyn_hooks.attach::<F>(&mut *storage);
+                let mut e = RegisteredStorage::new::<T>(storage);
+                e.clone_storage = clone_storage;
+                slot.insert(e);
+                true
+            }
+        }
+    }
+
+    /// Clone the map, failing with [`TraitTypeMapError::NotCloneable`] if any registered
+    /// type was not registered with [`register_cloneable_type_storage`](Self::register_cloneable_type_storage).
+    ///
+    /// Hooks are not cloned: the clone starts without any, so changes to it are not reported to
+    /// hooks registered on this map.
+    pub fn try_clone(&self) -> Result<Self, TraitTypeMapError>
+    where
+        S: Clone,
+    {
+        let mut entries =
+            IndexMap::with_capacity_and_hasher(self.entries.len(), self.entries.hasher().clone());
+        for (&id, e) in &self.entries {
+            entries.insert(id, e.try_clone()?);
+        }
+        Ok(Self {
+            entries,
+            views: self.views.clone(),
+            change_tick: self.change_tick,
+            dyn_hooks: DynHooks::new(),
+        })
+    }
+
+    /// Fail with [`TraitTypeMapError::NotCloneable`] if any registered type was not registered as cloneable.
+    pub(crate) fn check_cloneable(&self) -> Result<(), TraitTypeMapError> {
+        match self.entries.values().find(|e| e.clone_storage.is_none()) {
+            Some(e) => Err(TraitTypeMapError::NotCloneable {
+                type_name: e.type_name,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Remove the storage for `T`, returning it if `T` was registered.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use trait_type_map::{impl_trait_accessible, TraitTypeMap, VecFamily};
+    ///
+    /// trait Plugin {}
+    /// struct Physics;
+    /// impl Plugin for Physics {}
+    /// impl_trait_accessible!(dyn Plugin; Physics);
+    ///
+    /// let mut map: TraitTypeMap<dyn Plugin, VecFamily> = TraitTypeMap::new();
+    /// map.register_type_storage::<Physics>();
+    /// map.get_storage_mut::<Physics>().push(Physics);
+    ///
+    /// let storage = map.unregister_type_storage::<Physics>().unwrap();
+    /// assert_eq!(storage.len(), 1);
+    /// assert!(!map.contains_type::<Physics>());
+    ///
+    /// // The type can be registered again afterwards
+    /// map.register_type_storage::<Physics>();
+    /// ```
+    pub fn unregister_type_storage<T>(&mut self) -> Option<Box<F::Trait>>
+    where
+        T: 'static,
+    {
+        self.remove_storage_by_id(TypeId::of::<T>())
+    }
+
+    /// Remove the storage registered under `id`, returning it if it existed.
+    ///
+    /// The remaining types keep their relative registration order.
+    pub fn remove_storage_by_id(&mut self, id: TypeId) -> Option<Box<F::Trait>> {
+        for views in self.views.values_mut() {
+            views.remove(&id);
+        }
+        self.entries.shift_remove(&id).map(|e| e.storage)
+    }
+
+    /// Whether a storage is registered for `T`.
+    #[inline(always)]
+    pub fn contains_type<T>(&self) -> bool
+    where
+        T: 'static,
+    {
+        self.contains_type_id(TypeId::of::<T>())
+    }
+
+    /// Whether a storage is registered under `id`.
+    #[inline(always)]
+    pub fn contains_type_id(&self, id: TypeId) -> bool {
+        self.entries.contains_key(&id)
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `T` is not registered. See [`try_get_storage`](Self::try_get_storage).
+    #[inline(always)]
+    pub fn get_storage<T>(&self) -> &F::Storage<T>
+    where
+        T: 'static,
+    {
+        self.try_get_storage::<T>()
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `T` is not registered. See [`try_get_storage_mut`](Self::try_get_storage_mut).
+    #[inline(always)]
+    pub fn get_storage_mut<T>(&mut self) -> &mut F::Storage<T>
+    where
+        T: 'static,
+    {
+        self.try_get_storage_mut::<T>()
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    #[inline(always)]
+    pub fn try_get_storage<T>(&self) -> Result<&F::Storage<T>, TraitTypeMapError>
+    where
+        T: 'static,
+    {
+        let e = self
+            .entries
+            .get(&TypeId::of::<T>())
+            .ok_or(TraitTypeMapError::NotRegistered {
+                type_name: type_name::<T>(),
+            })?;
+        F::try_storage_ref::<T>(&*e.storage)
+    }
+
+    #[inline(always)]
+    pub fn try_get_storage_mut<T>(&mut self) -> Result<&mut F::Storage<T>, TraitTypeMapError>
+    where
+        T: 'static,
+    {
+        let e =
+            self.entries
+                .get_mut(&TypeId::of::<T>())
+                .ok_or(TraitTypeMapError::NotRegistered {
+                    type_name: type_name::<T>(),
+                })?;
+        F::try_storage_mut::<T>(&mut *e.storage)
+    }
+
+    /// Get mutable access to the storages of several distinct types at once.
+    ///
+    /// `Q` is a tuple of up to 8 types, e.g. `(Dog, Cat)`, and the result is a tuple of
+    /// `&mut F::Storage<_>` in the same order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a type is not registered or appears more than once in `Q`.
+    /// See [`try_get_many_storage_mut`](Self::try_get_many_storage_mut).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use trait_type_map::{impl_trait_accessible, TraitTypeMap, VecFamily};
+    ///
+    /// trait Unit {}
+    /// struct Dog(u32);
+    /// struct Cat(u32);
+    /// impl Unit for Dog {}
+    /// impl Unit for Cat {}
+    /// impl_trait_accessible!(dyn Unit; Dog, Cat);
+    ///
+    /// let mut map: TraitTypeMap<dyn Unit, VecFamily> = TraitTypeMap::new();
+    /// map.register_type_storage::<Dog>();
+    /// map.register_type_storage::<Cat>();
+    /// map.get_storage_mut::<Dog>().push(Dog(3));
+    ///
+    /// let (dogs, cats) = map.get_many_storage_mut::<(Dog, Cat)>();
+    /// for dog in &dogs.data {
+    ///     cats.push(Cat(dog.0 * 2));
+    /// }
+    /// assert_eq!(map.get_storage::<Cat>().get(0).0, 6);
+    ///
+    /// assert!(map.try_get_many_storage_mut::<(Dog, Dog)>().is_err());
+    /// ```
+    #[inline(always)]
+    pub fn get_many_storage_mut<'a, Q>(&'a mut self) -> Q::StoragesMut
+    where
+        Q: StorageTuple<'a, Dyn, F>,
+    {
+        self.try_get_many_storage_mut::<Q>()
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Non-panicking variant of [`get_many_storage_mut`](Self::get_many_storage_mut).
+    ///
+    /// Fails with [`TraitTypeMapError::AliasedStorage`] if a type appears more than once in `Q`.
+    #[inline(always)]
+    pub fn try_get_many_storage_mut<'a, Q>(
+        &'a mut self,
+    ) -> Result<Q::StoragesMut, TraitTypeMapError>
+    where
+        Q: StorageTuple<'a, Dyn, F>,
+    {
+        Q::try_get_many_mut(self)
     }
-}
 
-/// Trait object interface for single-value storage.
-///
-/// This allows accessing the stored value as a trait object without knowing the concrete type.
-pub trait TraitOptionStorage<Dyn: ?Sized>: Any {
-    fn is_some(&self) -> bool;
-    fn get(&self) -> Option<&Dyn>;
-    fn get_mut(&mut self) -> Option<&mut Dyn>;
-    fn take_boxed(&mut self) -> Option<Box<Dyn>>;
-    fn as_storage_any(&self) -> &dyn Any;
-    fn as_storage_any_mut(&mut self) -> &mut dyn Any;
-}
-impl<T: 'static, Dyn: ?Sized + 'static> TraitOptionStorage<Dyn> for OptionStorage<T, Dyn> {
-    fn is_some(&self) -> bool {
-        self.is_some()
+    /// Fetch family-trait storage by TypeId.
+    /// - For `VecFamily`: `&dyn TraitVecStorage<Dyn>`
+    /// - For `VecOptionFamily`: `&dyn TraitVecOptionStorage<Dyn>`
+    /// - For `OptionFamily`: `&dyn TraitOptionStorage<Dyn>`
+    #[inline(always)]
+    pub fn get_trait_storage(&self, id: TypeId) -> Option<&F::Trait> {
+        self.entries.get(&id).map(|e| &*e.storage)
     }
 
-    #[inline]
-    fn get(&self) -> Option<&Dyn> {
-        OptionStorage::<T, Dyn>::get_dyn(self)
+    #[inline(always)]
+    pub fn get_trait_storage_mut(&mut self, id: TypeId) -> Option<&mut F::Trait> {
+        self.entries.get_mut(&id).map(|e| &mut *e.storage)
     }
 
-    #[inline]
-    fn get_mut(&mut self) -> Option<&mut Dyn> {
-        OptionStorage::<T, Dyn>::get_dyn_mut(self)
+    /// Route a boxed trait object back into the storage of its concrete type, returning its index.
+    ///
+    /// The value is handed back if its concrete type is not registered, or if the family cannot
+    /// store values without a key (see [`StorageFamily::supports_push_any`]).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use trait_type_map::{impl_trait_accessible, AsAny, TraitTypeMap, VecFamily};
+    ///
+    /// trait Animal: AsAny {}
+    /// struct Dog;
+    /// struct Cat;
+    /// impl Animal for Dog {}
+    /// impl Animal for Cat {}
+    /// impl_trait_accessible!(dyn Animal; Dog, Cat);
+    ///
+    /// let mut map: TraitTypeMap<dyn Animal, VecFamily> = TraitTypeMap::new();
+    /// map.register_type_storage::<Dog>();
+    /// map.get_storage_mut::<Dog>().push(Dog);
+    ///
+    /// let dog = map.get_storage_mut::<Dog>().take_boxed(0);
+    /// assert_eq!(map.insert_boxed(dog).ok(), Some(0));
+    /// assert!(map.insert_boxed(Box::new(Cat)).is_err());
+    /// ```
+    pub fn insert_boxed(&mut self, value: Box<Dyn>) -> Result<usize, Box<Dyn>>
+    where
+        Dyn: AsAny,
+    {
+        if !F::supports_push_any() {
+            return Err(value);
+        }
+        let Some(e) = self.entries.get_mut(&(*value).as_any().type_id()) else {
+            return Err(value);
+        };
+        // Entries are keyed by the `TypeId` of their stored type, so this cannot mismatch
+        Ok(F::push_any(&mut *e.storage, value.into_any())
+            .unwrap_or_else(|_| unreachable!("storage does not match its TypeId")))
     }
 
+    /// Iterate over the `TypeId` and type name of every registered type, in registration order.
+    ///
+    /// Same as [`iter_types_in_registration_order`](Self::iter_types_in_registration_order).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::any::TypeId;
+    /// use trait_type_map::{impl_trait_accessible, TraitTypeMap, OptionFamily};
+    ///
+    /// trait Plugin {}
+    /// struct Physics;
+    /// impl Plugin for Physics {}
+    /// impl_trait_accessible!(dyn Plugin; Physics);
+    ///
+    /// let mut map: TraitTypeMap<dyn Plugin, OptionFamily> = TraitTypeMap::new();
+    /// map.register_type_storage::<Physics>();
+    ///
+    /// let (id, name) = map.registered_types().next().unwrap();
+    /// assert_eq!(id, TypeId::of::<Physics>());
+    /// assert!(name.ends_with("Physics"));
+    /// assert_eq!(map.type_name_of(id), Some(name));
+    /// ```
+    pub fn registered_types(&self) -> impl Iterator<Item = (TypeId, &'static str)> + '_ {
+        self.entries.iter().map(|(&id, e)| (id, e.type_name))
+    }
+
+    /// Name of the type registered under `id`, as given by [`std::any::type_name`].
     #[inline]
-    fn take_boxed(&mut self) -> Option<Box<Dyn>> {
-        OptionStorage::<T, Dyn>::take_boxed(self)
+    pub fn type_name_of(&self, id: TypeId) -> Option<&'static str> {
+        self.entries.get(&id).map(|e| e.type_name)
     }
 
-    fn as_storage_any(&self) -> &dyn Any {
-        self
+    /// Iterate over every stored value of every registered type as a trait object.
+    ///
+    /// Yields `(type_id, index, value)`, where `index` is the value's index within its
+    /// storage (always `0` for `OptionFamily`). Types are visited in registration order.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use trait_type_map::{impl_trait_accessible, TraitTypeMap, VecOptionFamily};
+    ///
+    /// trait Plugin {
+    ///     fn update(&mut self);
+    ///     fn ticks(&self) -> u32;
+    /// }
+    ///
+    /// struct Physics(u32);
+    /// impl Plugin for Physics {
+    ///     fn update(&mut self) { self.0 += 1; }
+    ///     fn ticks(&self) -> u32 { self.0 }
+    /// }
+    ///
+    /// struct Audio(u32);
+    /// impl Plugin for Audio {
+    ///     fn update(&mut self) { self.0 += 1; }
+    ///     fn ticks(&self) -> u32 { self.0 }
+    /// }
+    ///
+    /// impl_trait_accessible!(dyn Plugin; Physics, Audio);
+    ///
+    /// let mut map: TraitTypeMap<dyn Plugin, VecOptionFamily> = TraitTypeMap::new();
+    /// map.register_type_storage::<Physics>();
+    /// map.register_type_storage::<Audio>();
+    /// map.get_storage_mut::<Physics>().push(Physics(0));
+    /// map.get_storage_mut::<Audio>().push(Audio(10));
+    ///
+    /// for (_, _, plugin) in map.iter_dyn_mut() {
+    ///     plugin.update();
+    /// }
+    ///
+    /// let total: u32 = map.iter_dyn().map(|(_, _, plugin)| plugin.ticks()).sum();
+    /// assert_eq!(total, 12);
+    /// ```
+    pub fn iter_dyn(&self) -> impl Iterator<Item = (TypeId, usize, &Dyn)> + '_ {
+        self.entries
+            .iter()
+            .flat_map(|(&id, e)| F::iter_dyn(&*e.storage).map(move |(i, v)| (id, i, v)))
     }
 
-    fn as_storage_any_mut(&mut self) -> &mut dyn Any {
-        self
+    /// Mutable counterpart of [`iter_dyn`](Self::iter_dyn).
+    ///
+    /// There is no parallel counterpart: storages are boxed as `F::Trait`, which is not `Send`
+    /// for the built-in families, so they cannot be handed to other threads. Use
+    /// `SyncTraitTypeMap::par_for_each_dyn_mut` (with the `rayon` feature) instead.
+    pub fn iter_dyn_mut(&mut self) -> impl Iterator<Item = (TypeId, usize, &mut Dyn)> + '_ {
+        self.entries
+            .iter_mut()
+            .flat_map(|(&id, e)| F::iter_dyn_mut(&mut *e.storage).map(move |(i, v)| (id, i, v)))
     }
-}
 
-/// Marker type for the single-value storage family.
-pub struct OptionFamily;
+    /// Call `hook` with the index and value of every `T` inserted from now on.
+    ///
+    /// Storages fire their hooks from `push`, `set`, `take`, `take_boxed` and `swap_remove`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `T` is not registered.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::sync::{Arc, Mutex};
+    /// use trait_type_map::{impl_trait_accessible, TraitTypeMap, VecFamily};
+    ///
+    /// trait Body {
+    ///     fn x(&self) -> i32;
+    /// }
+    /// struct Ball(i32);
+    /// impl Body for Ball {
+    ///     fn x(&self) -> i32 { self.0 }
+    /// }
+    /// struct Wall(i32);
+    /// impl Body for Wall {
+    ///     fn x(&self) -> i32 { self.0 }
+    /// }
+    /// impl_trait_accessible!(dyn Body; Ball, Wall);
+    ///
+    /// let mut map: TraitTypeMap<dyn Body, VecFamily> = TraitTypeMap::new();
+    /// map.register_type_storage::<Ball>();
+    ///
+    /// let balls = Arc::new(Mutex::new(0));
+    /// let counter = balls.clone();
+    /// map.on_insert::<Ball>(move |_, _| *counter.lock().unwrap() += 1);
+    ///
+    /// // Trait object hooks also apply to types registered later
+    /// let index = Arc::new(Mutex::new(Vec::new()));
+    /// let inserted = index.clone();
+    /// let removed = index.clone();
+    /// map.on_insert_dyn(move |_, body| inserted.lock().unwrap().push(body.x()));
+    /// map.on_remove_dyn(move |_, body| removed.lock().unwrap().retain(|&x| x != body.x()));
+    /// map.register_type_storage::<Wall>();
+    ///
+    /// map.get_storage_mut::<Ball>().push(Ball(1));
+    /// map.get_storage_mut::<Wall>().push(Wall(5));
+    /// map.get_storage_mut::<Ball>().swap_remove(0);
+    ///
+    /// assert_eq!(*balls.lock().unwrap(), 1);
+    /// assert_eq!(*index.lock().unwrap(), [5]);
+    /// ```
+    pub fn on_insert<T>(&mut self, hook: impl Fn(usize, &T) + Send + Sync + 'static)
+    where
+        T: 'static,
+    {
+        F::add_insert_hook::<T>(self.get_storage_mut::<T>(), Arc::new(hook));
+    }
 
-/* =============== Storage family binding ================= */
+    /// Call `hook` with the index and value of every `T` removed from now on, before it is returned.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `T` is not registered.
+    pub fn on_remove<T>(&mut self, hook: impl Fn(usize, &T) + Send + Sync + 'static)
+    where
+        T: 'static,
+    {
+        F::add_remove_hook::<T>(self.get_storage_mut::<T>(), Arc::new(hook));
+    }
 
-/// Storage family trait that determines how values are stored. The family trait is generic over the **trait object** `Dyn`.
-/// Each impl chooses its trait type (`dyn TraitVecStorage<Dyn>`, `dyn TraitVecOptionStorage<Dyn>`, or `dyn TraitOptionStorage<Dyn>`)
-/// and its typed storage (`VecStorage<T, Dyn>`, `VecOptionStorage<T, Dyn>`, or `OptionStorage<T, Dyn>`).
-pub trait StorageFamily<Dyn: ?Sized + 'static> {
-    type Trait: ?Sized + 'static;
-    type Storage<T: 'static>: 'static;
+    /// Call `hook` with the index and trait object of every value inserted from now on, of any type.
+    pub fn on_insert_dyn(&mut self, hook: impl Fn(usize, &Dyn) + Send + Sync + 'static) {
+        let hook: Hook<Dyn> = Arc::new(hook);
+        for e in self.entries.values_mut() {
+            F::add_insert_hook_dyn(&mut *e.storage, hook.clone());
+        }
+        self.dyn_hooks.insert.push(hook);
+    }
 
-    fn make<T: 'static>(trait_accessor: TraitAccessor<T, Dyn>) -> Box<Self::Trait>;
-    fn storage_ref<T: 'static>(e: &Self::Trait) -> &Self::Storage<T>;
-    fn storage_mut<T: 'static>(e: &mut Self::Trait) -> &mut Self::Storage<T>;
-}
+    /// Call `hook` with the index and trait object of every value removed from now on, of any type.
+    pub fn on_remove_dyn(&mut self, hook: impl Fn(usize, &Dyn) + Send + Sync + 'static) {
+        let hook: Hook<Dyn> = Arc::new(hook);
+        for e in self.entries.values_mut() {
+            F::add_remove_hook_dyn(&mut *e.storage, hook.clone());
+        }
+        self.dyn_hooks.remove.push(hook);
+    }
 
-impl<D: ?Sized + 'static> StorageFamily<D> for VecFamily {
-    type Trait = dyn TraitVecStorage<D>;
-    type Storage<T: 'static> = VecStorage<T, D>;
+    /// Tick recorded by storages with change tracking enabled. Starts at `1`.
+    #[inline(always)]
+    pub fn change_tick(&self) -> u64 {
+        self.change_tick
+    }
 
-    fn make<T: 'static>(trait_accessor: TraitAccessor<T, D>) -> Box<Self::Trait> {
-        Box::new(VecStorage::<T, D>::new(trait_accessor))
+    /// Advance the change tick of the map and every storage, returning the new tick.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::any::TypeId;
+    /// use trait_type_map::{impl_trait_accessible, ChangeFlags, TraitTypeMap, VecOptionFamily};
+    ///
+    /// trait Sprite {}
+    /// struct Player(f32);
+    /// impl Sprite for Player {}
+    /// impl_trait_accessible!(dyn Sprite; Player);
+    ///
+    /// let mut map: TraitTypeMap<dyn Sprite, VecOptionFamily> = TraitTypeMap::new();
+    /// map.register_type_storage::<Player>();
+    /// map.get_storage_mut::<Player>().set_change_tracking(true);
+    /// map.get_storage_mut::<Player>().push(Player(0.0));
+    /// map.get_storage_mut::<Player>().push(Player(0.0));
+    ///
+    /// let frame = map.increment_change_tick();
+    /// map.get_storage_mut::<Player>().get_mut(1).unwrap().0 += 1.0;
+    ///
+    /// assert_eq!(map.changed_since(frame), [(TypeId::of::<Player>(), 1)]);
+    /// assert_eq!(map.drain_changes().len(), 2);
+    /// ```
+    pub fn increment_change_tick(&mut self) -> u64 {
+        self.change_tick += 1;
+        for e in self.entries.values_mut() {
+            F::set_change_tick(&mut *e.storage, self.change_tick);
+        }
+        self.change_tick
     }
 
-    fn storage_ref<T: 'static>(e: &Self::Trait) -> &Self::Storage<T> {
-        e.as_storage_any()
-            .downcast_ref::<VecStorage<T, D>>()
-            .expect("wrong T for VecFamily")
+    /// Drain the recorded changes of every storage with change tracking enabled, as `(TypeId, index, flags)`.
+    pub fn drain_changes(&mut self) -> Vec<(TypeId, usize, ChangeFlags)> {
+        self.entries
+            .iter_mut()
+            .flat_map(|(&id, e)| {
+                F::drain_changes(&mut *e.storage)
+                    .into_iter()
+                    .map(move |(i, flags)| (id, i, flags))
+            })
+            .collect()
     }
 
-    fn storage_mut<T: 'static>(e: &mut Self::Trait) -> &mut Self::Storage<T> {
-        e.as_storage_any_mut()
-            .downcast_mut::<VecStorage<T, D>>()
-            .expect("wrong T for VecFamily")
+    /// `(TypeId, index)` of every slot whose latest change happened at or after `tick`.
+    pub fn changed_since(&self, tick: u64) -> Vec<(TypeId, usize)> {
+        self.entries
+            .iter()
+            .flat_map(|(&id, e)| {
+                F::changed_since(&*e.storage, tick)
+                    .into_iter()
+                    .map(move |i| (id, i))
+            })
+            .collect()
     }
 }
 
-impl<D: ?Sized + 'static> StorageFamily<D> for VecOptionFamily {
-    type Trait = dyn TraitVecOptionStorage<D>;
-    type Storage<T: 'static> = VecOptionStorage<T, D>;
+/* ==================== Registration order ==================== */
 
-    fn make<T: 'static>(trait_accessor: TraitAccessor<T, D>) -> Box<Self::Trait> {
-        Box::new(VecOptionStorage::<T, D>::new(trait_accessor))
+impl<Dyn: ?Sized + 'static, F: StorageFamily<Dyn>, S: BuildHasher> TraitTypeMap<Dyn, F, S> {
+    /// Iterate over the `TypeId` and type name of every registered type, in registration order.
+    ///
+    /// The order is stable across runs and is also the order in which map-wide iteration such as
+    /// [`iter_dyn`](Self::iter_dyn) visits types. Unregistering a type keeps the relative order of
+    /// the others, and [`move_type`](Self::move_type) / [`swap_types`](Self::swap_types) change it explicitly.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::any::TypeId;
+    /// use trait_type_map::{impl_trait_accessible, TraitTypeMap, VecFamily};
+    ///
+    /// trait System {}
+    /// struct Input;
+    /// struct Physics;
+    /// struct Render;
+    /// impl System for Input {}
+    /// impl System for Physics {}
+    /// impl System for Render {}
+    /// impl_trait_accessible!(dyn System; Input, Physics, Render);
+    ///
+    /// let mut map: TraitTypeMap<dyn System, VecFamily> = TraitTypeMap::new();
+    /// map.register_type_storage::<Physics>();
+    /// map.register_type_storage::<Render>();
+    /// map.register_type_storage::<Input>();
+    ///
+    /// let order = |map: &TraitTypeMap<dyn System, VecFamily>| -> Vec<TypeId> {
+    ///     map.iter_types_in_registration_order().map(|(id, _)| id).collect()
+    /// };
+    /// let (input, physics, render) = (TypeId::of::<Input>(), TypeId::of::<Physics>(), TypeId::of::<Render>());
+    /// assert_eq!(order(&map), [physics, render, input]);
+    ///
+    /// // Input should run first
+    /// map.move_type::<Input>(0);
+    /// assert_eq!(order(&map), [input, physics, render]);
+    /// assert_eq!(map.type_position::<Render>(), Some(2));
+    ///
+    /// map.swap_types::<Physics, Render>();
+    /// assert_eq!(order(&map), [input, render, physics]);
+    /// ```
+    pub fn iter_types_in_registration_order(
+        &self,
+    ) -> impl Iterator<Item = (TypeId, &'static str)> + '_ {
+        self.registered_types()
     }
 
-    fn storage_ref<T: 'static>(e: &Self::Trait) -> &Self::Storage<T> {
-        e.as_storage_any()
-            .downcast_ref::<VecOptionStorage<T, D>>()
-            .expect("wrong T for VecOptionFamily")
+    /// Position of `T` in registration order.
+    #[inline]
+    pub fn type_position<T>(&self) -> Option<usize>
+    where
+        T: 'static,
+    {
+        self.type_position_by_id(TypeId::of::<T>())
     }
 
-    fn storage_mut<T: 'static>(e: &mut Self::Trait) -> &mut Self::Storage<T> {
-        e.as_storage_any_mut()
-            .downcast_mut::<VecOptionStorage<T, D>>()
-            .expect("wrong T for VecOptionFamily")
+    /// Position of the type registered under `id` in registration order.
+    #[inline]
+    pub fn type_position_by_id(&self, id: TypeId) -> Option<usize> {
+        self.entries.get_index_of(&id)
     }
-}
 
-impl<D: ?Sized + 'static> StorageFamily<D> for OptionFamily {
-    type Trait = dyn TraitOptionStorage<D>;
-    type Storage<T: 'static> = OptionStorage<T, D>;
+    /// Move `T` to position `to` in registration order, shifting the types in between.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `T` is not registered or `to` is out of bounds.
+    pub fn move_type<T>(&mut self, to: usize)
+    where
+        T: 'static,
+    {
+        self.try_move_type::<T>(to)
+            .unwrap_or_else(|err| panic!("{err}"));
+    }
 
-    fn make<T: 'static>(trait_accessor: TraitAccessor<T, D>) -> Box<Self::Trait> {
-        Box::new(OptionStorage::<T, D>::new(trait_accessor))
+    /// Checked counterpart of [`move_type`](Self::move_type).
+    pub fn try_move_type<T>(&mut self, to: usize) -> Result<(), TraitTypeMapError>
+    where
+        T: 'static,
+    {
+        let from = self.try_type_position::<T>()?;
+        let len = self.entries.len();
+        if to >= len {
+            return Err(TraitTypeMapError::IndexOutOfBounds { index: to, len });
+        }
+        self.entries.move_index(from, to);
+        Ok(())
     }
 
-    fn storage_ref<T: 'static>(e: &Self::Trait) -> &Self::Storage<T> {
-        e.as_storage_any()
-            .downcast_ref::<OptionStorage<T, D>>()
-            .expect("wrong T for OptionFamily")
+    /// Swap the positions of `A` and `B` in registration order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either type is not registered.
+    pub fn swap_types<A, B>(&mut self)
+    where
+        A: 'static,
+        B: 'static,
+    {
+        self.try_swap_types::<A, B>()
+            .unwrap_or_else(|err| panic!("{err}"));
     }
 
-    fn storage_mut<T: 'static>(e: &mut Self::Trait) -> &mut Self::Storage<T> {
-        e.as_storage_any_mut()
-            .downcast_mut::<OptionStorage<T, D>>()
-            .expect("wrong T for OptionFamily")
+    /// Checked counterpart of [`swap_types`](Self::swap_types).
+    pub fn try_swap_types<A, B>(&mut self) -> Result<(), TraitTypeMapError>
+    where
+        A: 'static,
+        B: 'static,
+    {
+        let a = self.try_type_position::<A>()?;
+        let b = self.try_type_position::<B>()?;
+        self.entries.swap_indices(a, b);
+        Ok(())
+    }
+
+    fn try_type_position<T: 'static>(&self) -> Result<usize, TraitTypeMapError> {
+        self.type_position::<T>()
+            .ok_or(TraitTypeMapError::NotRegistered {
+                type_name: type_name::<T>(),
+            })
     }
 }
 
-/* ===================== One map type ====================== */
+/* ================= Disjoint mutable borrows ================= */
 
-/// Trait for types that can be accessed via a trait object.
+/// A tuple of types whose storages can be borrowed mutably at the same time.
 ///
-/// Types implementing this trait can be stored in a `TraitTypeMap`.
-/// Use the `impl_trait_accessible!` macro to implement this trait.
-pub trait TraitAccessible<Dyn: ?Sized> {
-    fn get_accessor() -> TraitAccessor<Self, Dyn>
-    where
-        Self: Sized;
+/// Implemented for tuples of 1 to 8 types. Used by [`TraitTypeMap::get_many_storage_mut`].
+pub trait StorageTuple<'a, Dyn: ?Sized + 'static, F: StorageFamily<Dyn>> {
+    /// Tuple of `&'a mut F::Storage<_>`, one per type.
+    type StoragesMut;
+
+    fn try_get_many_mut<S: BuildHasher>(
+        map: &'a mut TraitTypeMap<Dyn, F, S>,
+    ) -> Result<Self::StoragesMut, TraitTypeMapError>;
 }
 
-/// A type-indexed map for storing values implementing a specific trait.
-///
-/// # Type Parameters
-///
-/// - `Dyn`: The trait object type (e.g., `dyn MyTrait`)
-/// - `F`: The storage family (`VecFamily`, `VecOptionFamily`, or `OptionFamily`)
-///
-/// # Examples
-///
-/// ```rust
-/// use trait_type_map::{impl_trait_accessible, TraitTypeMap, OptionFamily};
-///
-/// trait Animal {
-///     fn name(&self) -> &str;
-/// }
-///
-/// struct Dog { name: String }
-/// impl Animal for Dog {
-///     fn name(&self) -> &str { &self.name }
-/// }
-///
-/// impl_trait_accessible!(dyn Animal; Dog);
-///
-/// let mut map: TraitTypeMap<dyn Animal, OptionFamily> = TraitTypeMap::new();
-/// map.register_type_storage::<Dog>();
-/// map.get_storage_mut::<Dog>().set(Dog { name: "Rex".into() });
+macro_rules! impl_storage_tuple {
+    ($($ty:ident $var:ident),+) => {
+        impl<'a, Dyn, F, $($ty),+> StorageTuple<'a, Dyn, F> for ($($ty,)+)
+        where
+            Dyn: ?Sized + 'static,
+            F: StorageFamily<Dyn>,
+            $($ty: 'static, F::Storage<$ty>: 'a,)+
+        {
+            type StoragesMut = ($(&'a mut F::Storage<$ty>,)+);
+
+            fn try_get_many_mut<S: BuildHasher>(
+                map: &'a mut TraitTypeMap<Dyn, F, S>,
+            ) -> Result<Self::StoragesMut, TraitTypeMapError> {
+                let ids = [$(TypeId::of::<$ty>()),+];
+                let names = [$(type_name::<$ty>()),+];
+                // `get_disjoint_mut` panics on duplicate keys, so reject them first
+                for i in 1..ids.len() {
+                    if ids[..i].contains(&ids[i]) {
+                        return Err(TraitTypeMapError::AliasedStorage { type_name: names[i] });
+                    }
+                }
+                let [$($var),+] = map.entries.get_disjoint_mut([$(&TypeId::of::<$ty>()),+]);
+                Ok(($(
+                    F::try_storage_mut::<$ty>(
+                        &mut *$var
+                            .ok_or(TraitTypeMapError::NotRegistered { type_name: type_name::<$ty>() })?
+                            .storage,
+                    )?,
+                )+))
+            }
+        }
+    };
+}
+
+impl_storage_tuple!(A a);
+impl_storage_tuple!(A a, B b);
+impl_storage_tuple!(A a, B b, C c);
+impl_storage_tuple!(A a, B b, C c, D d);
+impl_storage_tuple!(A a, B b, C c, D d, E e);
+impl_storage_tuple!(A a, B b, C c, D d, E e, G g);
+impl_storage_tuple!(A a, B b, C c, D d, E e, G g, H h);
+impl_storage_tuple!(A a, B b, C c, D d, E e, G g, H h, I i);
+
+/* ==================== Lazy registration ==================== */
+
+/// A view into the storage slot of type `T` in a [`TraitTypeMap`], which may or may not be registered.
 ///
-/// if let Some(dog) = map.get_storage::<Dog>().get() {
-///     assert_eq!(dog.name(), "Rex");
-/// }
-/// ```
-pub struct TraitTypeMap<Dyn: ?Sized + 'static, F: StorageFamily<Dyn>> {
-    entries: AHashMap<TypeId, Box<F::Trait>>,
+/// Created by [`TraitTypeMap::entry`].
+pub struct StorageEntry<'a, Dyn: ?Sized + 'static, F: StorageFamily<Dyn>, T, S = DefaultHashBuilder>
+{
+    map: &'a mut TraitTypeMap<Dyn, F, S>,
+    _marker: PhantomData<fn() -> T>,
 }
 
-impl<Dyn: ?Sized + 'static, F: StorageFamily<Dyn>> Default for TraitTypeMap<Dyn, F> {
-    fn default() -> Self {
-        Self::new()
+impl<'a, Dyn: ?Sized + 'static, F: StorageFamily<Dyn>, T: 'static, S: BuildHasher>
+    StorageEntry<'a, Dyn, F, T, S>
+{
+    /// Whether a storage for `T` is already registered.
+    #[inline]
+    pub fn is_registered(&self) -> bool {
+        self.map.contains_type::<T>()
     }
-}
 
-impl<Dyn: ?Sized + 'static, F: StorageFamily<Dyn>> TraitTypeMap<Dyn, F> {
-    pub fn new() -> Self {
-        Self {
-            entries: AHashMap::new(),
+    /// Get the storage, registering it with `T`'s own accessor if it does not exist yet.
+    #[inline]
+    pub fn or_register(self) -> &'a mut F::Storage<T>
+    where
+        T: TraitAccessible<Dyn>,
+    {
+        self.or_register_with_fn(T::get_accessor)
+    }
+
+    /// Get the storage, registering it with the given accessor if it does not exist yet.
+    #[inline]
+    pub fn or_register_with(self, trait_accessor: TraitAccessor<T, Dyn>) -> &'a mut F::Storage<T> {
+        self.or_register_with_fn(|| trait_accessor)
+    }
+
+    /// Get the storage if it is registered, without registering it.
+    #[inline]
+    pub fn storage_mut(self) -> Option<&'a mut F::Storage<T>> {
+        self.map.try_get_storage_mut::<T>().ok()
+    }
+
+    fn or_register_with_fn(
+        self,
+        make_accessor: impl FnOnce() -> TraitAccessor<T, Dyn>,
+    ) -> &'a mut F::Storage<T> {
+        if !self.map.contains_type::<T>() {
+            self.map
+                .insert_storage::<T>(F::make::<T>(make_accessor()), None);
         }
+        self.map.get_storage_mut::<T>()
     }
+}
 
-    /// Create a new map with pre-allocated capacity for the given number of types.
-    /// This can improve performance when you know how many types you'll store.
-    pub fn with_capacity(capacity: usize) -> Self {
-        Self {
-            entries: AHashMap::with_capacity(capacity),
+/* ==================== Multi-trait views ==================== */
+
+type ViewIter<'a, V> = Box<dyn Iterator<Item = (usize, &'a V)> + 'a>;
+type ViewIterMut<'a, V> = Box<dyn Iterator<Item = (usize, &'a mut V)> + 'a>;
+
+/// Type-erased iteration over one storage as the view trait object `V`.
+struct ViewFns<S: ?Sized, V: ?Sized> {
+    iter: for<'a> fn(&'a S) -> ViewIter<'a, V>,
+    iter_mut: for<'a> fn(&'a mut S) -> ViewIterMut<'a, V>,
+}
+
+fn view_iter<Dyn, F, T, V>(e: &F::Trait) -> ViewIter<'_, V>
+where
+    Dyn: ?Sized + 'static,
+    F: StorageFamily<Dyn>,
+    T: TraitAccessible<V> + 'static,
+    V: ?Sized + 'static,
+{
+    let up_ref = T::get_accessor().up_ref;
+    Box::new(F::iter_indexed::<T>(F::storage_ref::<T>(e)).map(move |(i, v)| (i, up_ref(v))))
+}
+
+fn view_iter_mut<Dyn, F, T, V>(e: &mut F::Trait) -> ViewIterMut<'_, V>
+where
+    Dyn: ?Sized + 'static,
+    F: StorageFamily<Dyn>,
+    T: TraitAccessible<V> + 'static,
+    V: ?Sized + 'static,
+{
+    let up_mut = T::get_accessor().up_mut;
+    Box::new(F::iter_indexed_mut::<T>(F::storage_mut::<T>(e)).map(move |(i, v)| (i, up_mut(v))))
+}
+
+impl<Dyn: ?Sized + 'static, F: StorageFamily<Dyn>, S: BuildHasher> TraitTypeMap<Dyn, F, S> {
+    /// Make the values of `T` reachable through the extra trait object `V`, in addition to `Dyn`.
+    ///
+    /// Values stay in their single storage; the view only adds an accessor for [`iter_as`](Self::iter_as).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `T` is not registered. See [`try_register_view`](Self::try_register_view).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use trait_type_map::{impl_trait_accessible, TraitTypeMap, VecFamily};
+    ///
+    /// trait Component {}
+    /// trait Update {
+    ///     fn update(&mut self);
+    ///     fn ticks(&self) -> u32;
+    /// }
+    ///
+    /// struct Timer(u32);
+    /// impl Component for Timer {}
+    /// impl Update for Timer {
+    ///     fn update(&mut self) { self.0 += 1; }
+    ///     fn ticks(&self) -> u32 { self.0 }
+    /// }
+    ///
+    /// struct Label;
+    /// impl Component for Label {}
+    ///
+    /// impl_trait_accessible!(dyn Component; Timer, Label);
+    /// impl_trait_accessible!(dyn Update; Timer);
+    ///
+    /// let mut map: TraitTypeMap<dyn Component, VecFamily> = TraitTypeMap::new();
+    /// map.register_type_storage::<Timer>();
+    /// map.register_type_storage::<Label>();
+    /// map.register_view::<Timer, dyn Update>();
+    ///
+    /// map.get_storage_mut::<Timer>().push(Timer(0));
+    /// map.get_storage_mut::<Label>().push(Label);
+    ///
+    /// for (_, _, v) in map.iter_as_mut::<dyn Update>() {
+    ///     v.update();
+    /// }
+    ///
+    /// let ticks: Vec<u32> = map.iter_as::<dyn Update>().map(|(_, _, v)| v.ticks()).collect();
+    /// assert_eq!(ticks, [1]);
+    /// ```
+    pub fn register_view<T, V>(&mut self)
+    where
+        T: TraitAccessible<V> + 'static,
+        V: ?Sized + 'static,
+    {
+        if let Err(err) = self.try_register_view::<T, V>() {
+            panic!("{err}");
         }
     }
 
-    pub fn register_type_storage<T>(&mut self)
+    /// Register a view of `T` as `V`, failing with [`TraitTypeMapError::NotRegistered`]
+    /// instead of panicking if `T` has no storage.
+    pub fn try_register_view<T, V>(&mut self) -> Result<(), TraitTypeMapError>
     where
-        T: 'static + TraitAccessible<Dyn>,
+        T: TraitAccessible<V> + 'static,
+        V: ?Sized + 'static,
     {
-        let id = TypeId::of::<T>();
-        let inserted = self
-            .entries
-            .insert(id, F::make::<T>(T::get_accessor()))
-            .is_none();
-        assert!(inserted, "type already registered");
+        if !self.contains_type::<T>() {
+            return Err(TraitTypeMapError::NotRegistered {
+                type_name: type_name::<T>(),
+            });
+        }
+        let fns: ViewFns<F::Trait, V> = ViewFns {
+            iter: view_iter::<Dyn, F, T, V>,
+            iter_mut: view_iter_mut::<Dyn, F, T, V>,
+        };
+        self.views
+            .entry(TypeId::of::<V>())
+            .or_default()
+            .insert(TypeId::of::<T>(), Arc::new(fns));
+        Ok(())
     }
 
-    #[inline(always)]
-    pub fn get_storage<T>(&self) -> &F::Storage<T>
+    /// Whether `T` has been registered with a view as `V`.
+    pub fn has_view<T, V>(&self) -> bool
     where
         T: 'static,
+        V: ?Sized + 'static,
     {
-        let e = self
-            .entries
-            .get(&TypeId::of::<T>())
-            .expect("type not registered");
-        F::storage_ref::<T>(&**e)
+        self.views
+            .get(&TypeId::of::<V>())
+            .is_some_and(|views| views.contains_key(&TypeId::of::<T>()))
     }
 
-    #[inline(always)]
-    pub fn get_storage_mut<T>(&mut self) -> &mut F::Storage<T>
+    /// Iterate over every stored value whose type has a view as `V`, as `(TypeId, index, &V)`.
+    pub fn iter_as<V>(&self) -> impl Iterator<Item = (TypeId, usize, &V)> + '_
     where
-        T: 'static,
+        V: ?Sized + 'static,
     {
-        let e = self
-            .entries
-            .get_mut(&TypeId::of::<T>())
-            .expect("type not registered");
-        F::storage_mut::<T>(&mut **e)
+        let views = self.views.get(&TypeId::of::<V>());
+        self.entries
+            .iter()
+            .filter_map(move |(&id, e)| {
+                let fns = views?.get(&id)?.downcast_ref::<ViewFns<F::Trait, V>>()?;
+                Some((id, (fns.iter)(&*e.storage)))
+            })
+            .flat_map(|(id, iter)| iter.map(move |(i, v)| (id, i, v)))
     }
 
-    /// Fetch family-trait storage by TypeId.
-    /// - For `VecFamily`: `&dyn TraitVecStorage<Dyn>`
-    /// - For `VecOptionFamily`: `&dyn TraitVecOptionStorage<Dyn>`
-    /// - For `OptionFamily`: `&dyn TraitOptionStorage<Dyn>`
-    #[inline(always)]
-    pub fn get_trait_storage(&self, id: TypeId) -> Option<&F::Trait> {
-        self.entries.get(&id).map(|b| &**b)
+    /// Mutable counterpart of [`iter_as`](Self::iter_as).
+    ///
+    /// Values visited by storages with change tracking enabled are marked [`MODIFIED`](ChangeFlags::MODIFIED).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::any::TypeId;
+    /// use trait_type_map::{impl_trait_accessible, ChangeFlags, OptionFamily, TraitTypeMap};
+    ///
+    /// trait Plugin {}
+    /// trait Reset {
+    ///     fn reset(&mut self);
+    /// }
+    ///
+    /// struct Counter(u32);
+    /// impl Plugin for Counter {}
+    /// impl Reset for Counter {
+    ///     fn reset(&mut self) { self.0 = 0; }
+    /// }
+    ///
+    /// impl_trait_accessible!(dyn Plugin; Counter);
+    /// impl_trait_accessible!(dyn Reset; Counter);
+    ///
+    /// let mut map: TraitTypeMap<dyn Plugin, OptionFamily> = TraitTypeMap::new();
+    /// map.register_type_storage::<Counter>();
+    /// map.register_view::<Counter, dyn Reset>();
+    /// let storage = map.get_storage_mut::<Counter>();
+    /// storage.set_change_tracking(true);
+    /// storage.set(Counter(5));
+    /// map.drain_changes();
+    ///
+    /// for (_, _, v) in map.iter_as_mut::<dyn Reset>() {
+    ///     v.reset();
+    /// }
+    ///
+    /// assert_eq!(
+    ///     map.drain_changes(),
+    ///     [(TypeId::of::<Counter>(), 0, ChangeFlags::MODIFIED)]
+    /// );
+    /// ```
+    pub fn iter_as_mut<V>(&mut self) -> impl Iterator<Item = (TypeId, usize, &mut V)> + '_
+    where
+        V: ?Sized + 'static,
+    {
+        let views = self.views.get(&TypeId::of::<V>());
+        self.entries
+            .iter_mut()
+            .filter_map(move |(&id, e)| {
+                let fns = views?.get(&id)?.downcast_ref::<ViewFns<F::Trait, V>>()?;
+                Some((id, (fns.iter_mut)(&mut *e.storage)))
+            })
+            .flat_map(|(id, iter)| iter.map(move |(i, v)| (id, i, v)))
     }
+}
 
-    #[inline(always)]
-    pub fn get_trait_storage_mut(&mut self, id: TypeId) -> Option<&mut F::Trait> {
-        self.entries.get_mut(&id).map(|b| &mut **b)
+/* ==================== Priority iteration ==================== */
+
+/// Next value of one storage in a [`PriorityMerge`].
+///
+/// Ordered so that `BinaryHeap` pops the lowest priority first, breaking ties by source.
+struct MergeHead<'a, P, V> {
+    priority: &'a P,
+    source: usize,
+    value: V,
+}
+
+impl<P: Ord, V> PartialEq for MergeHead<'_, P, V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<P: Ord, V> Eq for MergeHead<'_, P, V> {}
+
+impl<P: Ord, V> PartialOrd for MergeHead<'_, P, V> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<P: Ord, V> Ord for MergeHead<'_, P, V> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .priority
+            .cmp(self.priority)
+            .then(other.source.cmp(&self.source))
+    }
+}
+
+type PrioritizedIter<'a, P, V> = Box<dyn Iterator<Item = (&'a P, V)> + 'a>;
+
+/// K-way merge of sorted per-type iterators into one stream ordered by priority.
+struct PriorityMerge<'a, P, V> {
+    sources: Vec<(TypeId, PrioritizedIter<'a, P, V>)>,
+    heap: BinaryHeap<MergeHead<'a, P, V>>,
+}
+
+impl<'a, P: Ord, V> PriorityMerge<'a, P, V> {
+    fn new(mut sources: Vec<(TypeId, PrioritizedIter<'a, P, V>)>) -> Self {
+        let mut heap = BinaryHeap::with_capacity(sources.len());
+        for (source, (_, iter)) in sources.iter_mut().enumerate() {
+            if let Some((priority, value)) = iter.next() {
+                heap.push(MergeHead {
+                    priority,
+                    source,
+                    value,
+                });
+            }
+        }
+        Self { sources, heap }
+    }
+}
+
+impl<'a, P: Ord, V> Iterator for PriorityMerge<'a, P, V> {
+    type Item = (TypeId, &'a P, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let head = self.heap.pop()?;
+        let (id, iter) = &mut self.sources[head.source];
+        if let Some((priority, value)) = iter.next() {
+            self.heap.push(MergeHead {
+                priority,
+                source: head.source,
+                value,
+            });
+        }
+        Some((*id, head.priority, head.value))
+    }
+}
+
+impl<Dyn: ?Sized + 'static, P: Ord + Clone + 'static, S: BuildHasher>
+    TraitTypeMap<Dyn, OrderedFamily<P>, S>
+{
+    /// Iterate over every stored value of every type as `(TypeId, &priority, &Dyn)`, in priority order.
+    ///
+    /// Values of different types with equal priorities are ordered by the types' registration order
+    /// (see [`move_type`](Self::move_type) to change it).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use trait_type_map::{impl_trait_accessible, OrderedFamily, TraitTypeMap};
+    ///
+    /// trait System {
+    ///     fn name(&self) -> &str;
+    /// }
+    /// struct Input;
+    /// struct Physics;
+    /// struct Render;
+    /// impl System for Input {
+    ///     fn name(&self) -> &str { "input" }
+    /// }
+    /// impl System for Physics {
+    ///     fn name(&self) -> &str { "physics" }
+    /// }
+    /// impl System for Render {
+    ///     fn name(&self) -> &str { "render" }
+    /// }
+    /// impl_trait_accessible!(dyn System; Input, Physics, Render);
+    ///
+    /// let mut map: TraitTypeMap<dyn System, OrderedFamily<i32>> = TraitTypeMap::new();
+    /// map.register_type_storage::<Render>();
+    /// map.register_type_storage::<Physics>();
+    /// map.register_type_storage::<Input>();
+    /// map.get_storage_mut::<Render>().insert(100, Render);
+    /// map.get_storage_mut::<Physics>().insert(10, Physics);
+    /// map.get_storage_mut::<Physics>().insert(50, Physics);
+    /// map.get_storage_mut::<Input>().insert(0, Input);
+    ///
+    /// let order: Vec<_> = map
+    ///     .iter_by_priority()
+    ///     .map(|(_, &priority, system)| (priority, system.name()))
+    ///     .collect();
+    /// assert_eq!(order, [(0, "input"), (10, "physics"), (50, "physics"), (100, "render")]);
+    /// ```
+    pub fn iter_by_priority(&self) -> impl Iterator<Item = (TypeId, &P, &Dyn)> + '_ {
+        PriorityMerge::new(
+            self.entries
+                .iter()
+                .map(|(&id, e)| (id, e.storage.iter_prioritized()))
+                .collect(),
+        )
+    }
+
+    /// Mutable counterpart of [`iter_by_priority`](Self::iter_by_priority).
+    pub fn iter_by_priority_mut(&mut self) -> impl Iterator<Item = (TypeId, &P, &mut Dyn)> + '_ {
+        PriorityMerge::new(
+            self.entries
+                .iter_mut()
+                .map(|(&id, e)| (id, e.storage.iter_prioritized_mut()))
+                .collect(),
+        )
     }
 }
@@ -0,0 +1,160 @@
+//! Macros implementing `TraitAccessible` for [`trait_type_map`](https://docs.rs/trait_type_map).
+//!
+//! Enable the `derive` feature of `trait_type_map` instead of depending on this crate directly.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::parse::Parser;
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::{parse_macro_input, DeriveInput, Token, Type, TypeTraitObject};
+
+/// Implement `TraitAccessible` for the annotated type, once per listed trait object.
+///
+/// Generic types are supported: every impl is bounded on the type implementing the trait.
+///
+/// # Examples
+///
+/// ```rust
+/// use trait_type_map::{trait_accessible, TraitTypeMap, VecFamily};
+///
+/// trait Shape {
+///     fn area(&self) -> f32;
+/// }
+/// trait Named {
+///     fn name(&self) -> &str;
+/// }
+///
+/// #[trait_accessible(dyn Shape, dyn Named)]
+/// struct Square(f32);
+/// impl Shape for Square {
+///     fn area(&self) -> f32 { self.0 * self.0 }
+/// }
+/// impl Named for Square {
+///     fn name(&self) -> &str { "square" }
+/// }
+///
+/// #[trait_accessible(dyn Shape)]
+/// struct Scaled<S: Shape>(S, f32);
+/// impl<S: Shape> Shape for Scaled<S> {
+///     fn area(&self) -> f32 { self.0.area() * self.1 }
+/// }
+///
+/// let mut map: TraitTypeMap<dyn Shape, VecFamily> = TraitTypeMap::new();
+/// map.register_type_storage::<Scaled<Square>>();
+/// let idx = map.get_storage_mut::<Scaled<Square>>().push(Scaled(Square(2.0), 0.5));
+/// assert_eq!(map.get_storage::<Scaled<Square>>().get_dyn(idx).area(), 2.0);
+/// ```
+#[proc_macro_attribute]
+pub fn trait_accessible(args: TokenStream, input: TokenStream) -> TokenStream {
+    let item = parse_macro_input!(input as DeriveInput);
+    let impls = Punctuated::<Type, Token![,]>::parse_terminated
+        .parse(args)
+        .and_then(|traits| expand(&item, traits));
+    match impls {
+        Ok(impls) => quote! { #item #impls }.into(),
+        Err(err) => {
+            let err = err.to_compile_error();
+            quote! { #item #err }.into()
+        }
+    }
+}
+
+/// Derive `TraitAccessible` for the trait objects listed in `#[accessible_as(...)]`.
+///
+/// Equivalent to [`macro@trait_accessible`], for codebases that prefer derives.
+///
+/// # Examples
+///
+/// ```rust
+/// use trait_type_map::{TraitAccessible, TraitTypeMap, OptionFamily};
+///
+/// trait Plugin {
+///     fn name(&self) -> &str;
+/// }
+///
+/// #[derive(TraitAccessible)]
+/// #[accessible_as(dyn Plugin)]
+/// struct Logger;
+/// impl Plugin for Logger {
+///     fn name(&self) -> &str { "logger" }
+/// }
+///
+/// let mut map: TraitTypeMap<dyn Plugin, OptionFamily> = TraitTypeMap::new();
+/// map.register_type_storage::<Logger>();
+/// map.get_storage_mut::<Logger>().set(Logger);
+/// assert_eq!(map.get_storage::<Logger>().get_dyn().unwrap().name(), "logger");
+/// ```
+#[proc_macro_derive(TraitAccessible, attributes(accessible_as))]
+pub fn derive_trait_accessible(input: TokenStream) -> TokenStream {
+    let item = parse_macro_input!(input as DeriveInput);
+    let mut traits = Punctuated::<Type, Token![,]>::new();
+    for attr in item
+        .attrs
+        .iter()
+        .filter(|a| a.path().is_ident("accessible_as"))
+    {
+        match attr.parse_args_with(Punctuated::<Type, Token![,]>::parse_terminated) {
+            Ok(parsed) => traits.extend(parsed),
+            Err(err) => return err.to_compile_error().into(),
+        }
+    }
+    if traits.is_empty() {
+        return syn::Error::new(
+            item.ident.span(),
+            "missing `#[accessible_as(dyn Trait, ...)]` attribute",
+        )
+        .to_compile_error()
+        .into();
+    }
+    expand(&item, traits)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand(item: &DeriveInput, traits: Punctuated<Type, Token![,]>) -> syn::Result<TokenStream2> {
+    if traits.is_empty() {
+        return Err(syn::Error::new(
+            item.ident.span(),
+            "expected at least one trait object, e.g. `dyn Trait`",
+        ));
+    }
+    let ident = &item.ident;
+    let (impl_generics, ty_generics, where_clause) = item.generics.split_for_impl();
+    let predicates: Vec<_> = where_clause
+        .into_iter()
+        .flat_map(|w| &w.predicates)
+        .collect();
+
+    traits
+        .iter()
+        .map(|dyn_ty| {
+            let Type::TraitObject(TypeTraitObject {
+                dyn_token: Some(_),
+                bounds,
+            }) = dyn_ty
+            else {
+                return Err(syn::Error::new(
+                    dyn_ty.span(),
+                    "expected a trait object, e.g. `dyn Trait`",
+                ));
+            };
+            Ok(quote! {
+                impl #impl_generics ::trait_type_map::TraitAccessible<#dyn_ty> for #ident #ty_generics
+                where
+                    #(#predicates,)*
+                    Self: #bounds + 'static,
+                {
+                    fn get_accessor() -> ::trait_type_map::TraitAccessor<Self, #dyn_ty> {
+                        ::trait_type_map::TraitAccessor {
+                            up_ref: |v| v,
+                            up_mut: |v| v,
+                            up_box: |v| ::std::boxed::Box::new(v),
+                        }
+                    }
+                }
+            })
+        })
+        .collect()
+}